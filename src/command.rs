@@ -1,3 +1,5 @@
+use crate::domain::roll::RollContext;
+use crate::domain::{ability_score, currency, effect};
 use std::path::PathBuf;
 
 #[derive(clap::Parser, Clone, Debug, PartialEq)]
@@ -7,6 +9,11 @@ pub enum RootCmd {
         #[clap(subcommand)]
         cmd: CharacterCmd,
     },
+    /// Roll dice written in standard notation, e.g. 2d6+1d4+3, d20 or 4d6kh3
+    Roll {
+        #[clap(allow_hyphen_values = true)]
+        expr: String,
+    },
     Completions {
         #[clap()]
         shell: clap_complete::Shell,
@@ -26,38 +33,173 @@ pub enum CharacterCmd {
     HitPoints {
         #[clap(subcommand)]
         cmd: HitPointsCmd
+    },
+    AbilityScores {
+        #[clap(subcommand)]
+        cmd: AbilityScoresCmd
+    },
+    ArmorClass {
+        #[clap(subcommand)]
+        cmd: ArmorClassCmd
+    },
+    Rest {
+        #[clap(subcommand)]
+        cmd: RestCmd
+    },
+    Resource {
+        #[clap(subcommand)]
+        cmd: ResourceCmd
+    },
+    SpellSlots {
+        #[clap(subcommand)]
+        cmd: SpellSlotsCmd
+    },
+    Spells {
+        #[clap(subcommand)]
+        cmd: SpellsCmd
+    },
+    Concentration {
+        #[clap(subcommand)]
+        cmd: ConcentrationCmd
+    },
+    Condition {
+        #[clap(subcommand)]
+        cmd: ConditionCmd
+    },
+    Wallet {
+        #[clap(subcommand)]
+        cmd: WalletCmd
+    },
+    /// Show the passive score of a skill: 10 plus the skill's bonuses, and 5 more or less with
+    /// advantage or disadvantage
+    Passive {
+        #[clap(arg_enum)]
+        skill: Skill,
+    },
+    /// Cast a spell from the spellbook, using a spell slot and rolling its attack and damage
+    Cast {
+        spell: String,
+        /// Cast the spell with a higher level spell slot
+        #[clap(long)]
+        level: Option<isize>,
+        /// Use a Warlock pact slot
+        #[clap(long)]
+        pact: bool,
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
     }
 }
 #[derive(clap::Subcommand, Clone, Debug, PartialEq)]
 pub enum RollCmd {
     Skill {
         #[clap(arg_enum)]
-        skill: Skill
+        skill: Skill,
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
     },
     Ability {
         #[clap(arg_enum)]
-        ability: Ability
+        ability: Ability,
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
     },
     SavingThrow {
         #[clap(arg_enum)]
-        ability: Ability
+        ability: Ability,
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
+    },
+    /// Roll initiative, a Dexterity check
+    Initiative {
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
+    },
+    /// Roll to hit and for damage with a weapon in the inventory
+    Attack {
+        #[clap()]
+        item: PathBuf,
+        /// Throw the weapon, using its thrown range
+        #[clap(long)]
+        thrown: bool,
+        /// Wield a versatile weapon with two hands
+        #[clap(long)]
+        two_handed: bool,
+        /// Remove the thrown weapon or the ammunition used from the inventory
+        #[clap(long)]
+        consume: bool,
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
+    }
+}
+
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct AdvantageArgs {
+    /// Roll the d20 twice and keep the higher result
+    #[clap(long)]
+    advantage: bool,
+    /// Roll the d20 twice and keep the lower result
+    #[clap(long)]
+    disadvantage: bool,
+}
+
+impl AdvantageArgs {
+    pub fn to_advantages(&self) -> Vec<effect::Advantage> {
+        let mut advantages = vec![];
+        if self.advantage {
+            advantages.push(effect::Advantage::Advantage);
+        }
+        if self.disadvantage {
+            advantages.push(effect::Advantage::Disadvantage);
+        }
+        advantages
     }
 }
 
 impl RollCmd {
     pub fn to_path(&self) -> Vec<String> {
         match self {
-            RollCmd::Skill { skill } => {
+            RollCmd::Skill { skill, .. } => {
                 vec!["skill".to_string(), skill.to_path()]
             },
-            RollCmd::Ability { ability } => {
+            RollCmd::Ability { ability, .. } => {
                 vec!["ability".to_string(), format!("{:?}", ability).to_lowercase()]
             },
-            RollCmd::SavingThrow { ability } => {
+            RollCmd::SavingThrow { ability, .. } => {
                 vec!["saving-throw".to_string(), format!("{:?}", ability).to_lowercase()]
+            },
+            RollCmd::Initiative { .. } => {
+                vec!["initiative".to_string()]
+            },
+            RollCmd::Attack { .. } => {
+                vec!["attack".to_string()]
             }
         }
     }
+
+    pub fn to_context(&self) -> RollContext {
+        match self {
+            RollCmd::Skill { skill, advantage } => RollContext {
+                ability: Some(skill.ability()),
+                advantages: advantage.to_advantages(),
+                ..RollContext::default()
+            },
+            RollCmd::Ability { ability, advantage }
+            | RollCmd::SavingThrow { ability, advantage } => RollContext {
+                ability: Some(ability.to_ability()),
+                advantages: advantage.to_advantages(),
+                ..RollContext::default()
+            },
+            RollCmd::Initiative { advantage } => RollContext {
+                ability: Some(ability_score::Ability::Dexterity),
+                advantages: advantage.to_advantages(),
+                ..RollContext::default()
+            },
+            RollCmd::Attack { advantage, .. } => RollContext {
+                advantages: advantage.to_advantages(),
+                ..RollContext::default()
+            },
+        }
+    }
 }
 
 #[derive(clap::ArgEnum, Clone, Debug, PartialEq)]
@@ -70,6 +212,19 @@ pub enum Ability {
     Charisma,
 }
 
+impl Ability {
+    pub fn to_ability(&self) -> ability_score::Ability {
+        match self {
+            Ability::Strength => ability_score::Ability::Strength,
+            Ability::Dexterity => ability_score::Ability::Dexterity,
+            Ability::Constitution => ability_score::Ability::Constitution,
+            Ability::Intelligence => ability_score::Ability::Intelligence,
+            Ability::Wisdom => ability_score::Ability::Wisdom,
+            Ability::Charisma => ability_score::Ability::Charisma,
+        }
+    }
+}
+
 #[derive(clap::ArgEnum, Clone, Debug, PartialEq)]
 pub enum Skill {
     Acrobatics,
@@ -100,6 +255,194 @@ impl Skill {
             _ => format!("{:?}", self).to_lowercase()
         }
     }
+
+    pub fn ability(&self) -> ability_score::Ability {
+        match self {
+            Skill::Athletics => ability_score::Ability::Strength,
+            Skill::Acrobatics | Skill::SleightOfHand | Skill::Stealth => {
+                ability_score::Ability::Dexterity
+            }
+            Skill::Arcana
+            | Skill::History
+            | Skill::Investigation
+            | Skill::Nature
+            | Skill::Religion => ability_score::Ability::Intelligence,
+            Skill::AnimalHandling
+            | Skill::Insight
+            | Skill::Medicine
+            | Skill::Perception
+            | Skill::Survival => ability_score::Ability::Wisdom,
+            Skill::Deception | Skill::Intimidation | Skill::Performance | Skill::Persuasion => {
+                ability_score::Ability::Charisma
+            }
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum AbilityScoresCmd {
+    /// Show effective ability scores and the effects that changed them
+    Show {
+        #[clap(arg_enum)]
+        ability: Option<Ability>
+    },
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum ArmorClassCmd {
+    /// Show armor class and everything that contributes to it
+    Show,
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum ConditionCmd {
+    /// Add a condition, or another level of a leveled condition such as exhaustion
+    Add {
+        name: String,
+        /// Set a leveled condition to this level
+        #[clap(long)]
+        level: Option<isize>,
+    },
+    /// Remove a condition, or a single level of a leveled condition
+    Remove { name: String },
+    Show,
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum WalletCmd {
+    /// Add coins, e.g. `add 12gp 5sp`
+    Add {
+        #[clap(required = true)]
+        amount: Vec<String>,
+    },
+    /// Spend coins, breaking larger coins for change when needed
+    Spend {
+        #[clap(required = true)]
+        amount: Vec<String>,
+    },
+    /// Exchange coins for another kind of coin, e.g. `convert 150cp --to gp`
+    Convert {
+        #[clap(required = true)]
+        amount: Vec<String>,
+        #[clap(long, arg_enum)]
+        to: Coin,
+    },
+    /// Divide loot into equal shares for the party, e.g. `split 4 3pp 12gp`
+    Split {
+        shares: isize,
+        #[clap(required = true)]
+        amount: Vec<String>,
+        /// Add one share to the wallet
+        #[clap(long)]
+        take: bool,
+    },
+    Show,
+}
+
+#[derive(clap::ArgEnum, Clone, Debug, PartialEq)]
+pub enum Coin {
+    Cp,
+    Sp,
+    Ep,
+    Gp,
+    Pp,
+}
+
+impl Coin {
+    pub fn to_coin(&self) -> currency::Coin {
+        match self {
+            Coin::Cp => currency::Coin::Copper,
+            Coin::Sp => currency::Coin::Silver,
+            Coin::Ep => currency::Coin::Electrum,
+            Coin::Gp => currency::Coin::Gold,
+            Coin::Pp => currency::Coin::Platinum,
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum ConcentrationCmd {
+    /// Show the spell being concentrated on
+    Show,
+    /// Stop concentrating on the current spell
+    End,
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum SpellsCmd {
+    /// Add a spell from the compendium to the spellbook
+    Add {
+        spell: String,
+        /// The class the spell is cast with, by default the first spellcasting class
+        #[clap(long)]
+        class: Option<String>,
+        #[clap(long)]
+        prepared: bool,
+    },
+    Remove {
+        spell: String,
+    },
+    Prepare {
+        spell: String,
+    },
+    Unprepare {
+        spell: String,
+    },
+    Show,
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum SpellSlotsCmd {
+    /// Use a spell slot of the given level
+    Use {
+        level: Option<isize>,
+        /// Use a Warlock pact slot instead
+        #[clap(long)]
+        pact: bool,
+    },
+    /// Regain a spell slot of the given level, or every spell slot without one
+    Regain {
+        level: Option<isize>,
+        /// Regain every Warlock pact slot instead
+        #[clap(long)]
+        pact: bool,
+    },
+    /// Show the remaining spell slots
+    Show,
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum ResourceCmd {
+    /// Spend uses of a resource granted by a feature
+    Use {
+        name: String,
+        #[clap(default_value = "1")]
+        count: isize,
+    },
+    /// Regain uses of a resource, by default all of them
+    Regain {
+        name: String,
+        count: Option<isize>,
+    },
+    /// Show the remaining uses of one or every resource
+    Show {
+        name: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Clone, Debug, PartialEq)]
+pub enum RestCmd {
+    /// Take a short rest, spending hit dice to regain hit points
+    Short {
+        /// The number of hit dice to spend
+        #[clap(default_value = "0")]
+        hit_dice: isize,
+        /// Spend hit dice with this many sides, by default the largest remaining
+        #[clap(long)]
+        sides: Option<isize>,
+    },
+    /// Take a long rest, regaining hit points, hit dice and everything else that recharges
+    Long,
 }
 
 #[derive(clap::Subcommand, Clone, Debug, PartialEq)]
@@ -113,16 +456,32 @@ pub enum HitPointsCmd {
         #[clap()]
         hit_points: isize
     },
+    /// Take damage, e.g. `remove 14 --type fire` or `remove 8 slashing + 3 fire`
     Remove {
-        #[clap()]
-        hit_points: isize
+        #[clap(required = true)]
+        damage: Vec<String>,
+        /// The damage type of amounts given without one
+        #[clap(long = "type")]
+        damage_type: Option<String>,
     },
+    /// Gain temporary hit points, which absorb damage first and don't stack
     AddTemporary {
         #[clap()]
         hit_points: isize
     },
     ResetTemporary,
-    Reset
+    /// Raise or lower the hit point maximum, along with current hit points, e.g. from Aid
+    ModifyMax {
+        #[clap(allow_hyphen_values = true)]
+        hit_points: isize
+    },
+    ResetMaxModifier,
+    Reset,
+    /// Roll a death saving throw while at 0 hit points
+    DeathSave {
+        #[clap(flatten)]
+        advantage: AdvantageArgs,
+    },
 }
 
 #[derive(clap::Subcommand, Clone, Debug, PartialEq)]
@@ -140,7 +499,38 @@ pub enum InventoryCmd {
         #[clap()]
         count: isize,
     },
-    Show,
+    Show {
+        /// Show the weight of each stack, with subtotals for each container
+        #[clap(long)]
+        weight: bool,
+        /// Show the value of each stack, with subtotals for each container
+        #[clap(long)]
+        value: bool,
+    },
+    /// Equip an item, such as armor or a shield, so its effects apply
+    Equip {
+        #[clap()]
+        name: PathBuf,
+    },
+    Unequip {
+        #[clap()]
+        name: PathBuf,
+    },
+    /// Show the weight carried, carrying capacity and encumbrance
+    Weight,
+    /// Move an item or container into a container, or to a new path with --rename
+    Move {
+        #[clap()]
+        from: PathBuf,
+        #[clap()]
+        to: PathBuf,
+        /// How many to move from a stack, all of them by default
+        #[clap()]
+        count: Option<isize>,
+        /// Treat the destination as the item's new path, rather than a container to move it into
+        #[clap(long)]
+        rename: bool,
+    },
     Container {
         #[clap(subcommand)]
         cmd: InventoryContainerCmd,
@@ -151,9 +541,22 @@ pub enum InventoryContainerCmd {
     Add {
         #[clap()]
         name: PathBuf,
+        /// The weight of the empty container in pounds
+        #[clap(long)]
+        weight: Option<f64>,
+        /// The contents don't count towards the weight carried, like a Bag of Holding
+        #[clap(long)]
+        weightless_contents: bool,
     },
+    /// Remove a container, which must be empty unless its contents are spilled or discarded
     Remove {
         #[clap()]
         name: PathBuf,
+        /// Move the contents into the parent container
+        #[clap(long)]
+        spill: bool,
+        /// Remove the contents along with the container
+        #[clap(long, conflicts_with = "spill")]
+        discard: bool,
     },
 }
\ No newline at end of file
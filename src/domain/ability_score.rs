@@ -1,5 +1,10 @@
+use crate::domain::effect::AbilityScoreBonus;
+use crate::render::Render;
+use anyhow::Result;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter};
+use std::io::Write;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Ability {
@@ -93,6 +98,9 @@ impl AbilityScore {
     pub fn of(value: isize) -> AbilityScore {
         AbilityScore { value }
     }
+    pub fn value(&self) -> isize {
+        self.value
+    }
     pub fn modifier(&self) -> isize {
         if self.value < 10 {
             (self.value - 11) / 2
@@ -102,9 +110,83 @@ impl AbilityScore {
     }
 }
 
+/// An ability score along with every effect that changed it from the base score.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AbilityScoreResult {
+    ability: Ability,
+    base: AbilityScore,
+    bonuses: Vec<(Vec<String>, AbilityScoreBonus)>,
+}
+
+impl AbilityScoreResult {
+    pub fn new(
+        ability: Ability,
+        base: AbilityScore,
+        bonuses: Vec<(Vec<String>, AbilityScoreBonus)>,
+    ) -> AbilityScoreResult {
+        AbilityScoreResult {
+            ability,
+            base,
+            bonuses,
+        }
+    }
+
+    /// Modifiers are added to the base score, then `Become` bonuses act as a floor.
+    pub fn score(&self) -> AbilityScore {
+        let modified = self.base.value
+            + self
+                .bonuses
+                .iter()
+                .map(|(_, bonus)| match bonus {
+                    AbilityScoreBonus::Modifier { modifier } => *modifier,
+                    AbilityScoreBonus::Become { .. } => 0,
+                })
+                .sum::<isize>();
+
+        let floor = self
+            .bonuses
+            .iter()
+            .flat_map(|(_, bonus)| match bonus {
+                AbilityScoreBonus::Become { value } => Some(*value),
+                AbilityScoreBonus::Modifier { .. } => None,
+            })
+            .max();
+
+        AbilityScore::of(floor.map_or(modified, |floor| floor.max(modified)))
+    }
+}
+
+impl Render for AbilityScoreResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}{}\n", <dyn Render>::tab(indent), self.ability))?;
+        out.write_fmt(format_args!(
+            "{}base: {}\n",
+            <dyn Render>::tab(indent + 1),
+            self.base.value
+        ))?;
+        for (path, bonus) in &self.bonuses {
+            out.write_fmt(format_args!(
+                "{}{}: {}\n",
+                <dyn Render>::tab(indent + 1),
+                path.iter().join(" / "),
+                bonus
+            ))?;
+        }
+        let score = self.score();
+        out.write_fmt(format_args!(
+            "{}Total: {} ({:+})\n",
+            <dyn Render>::tab(indent),
+            score.value,
+            score.modifier()
+        ))?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::AbilityScore;
+    use super::{Ability, AbilityScore, AbilityScoreResult};
+    use crate::domain::effect::AbilityScoreBonus;
 
     fn assert_modfier(value: isize, modifier: isize) {
         assert_eq!(
@@ -142,4 +224,43 @@ mod test {
         .into_iter()
         .for_each(|(value, modifier)| assert_modfier(value, modifier))
     }
+
+    #[test]
+    fn test_bonuses() {
+        let source = vec!["feature".to_string()];
+        let score = |base, bonuses: Vec<AbilityScoreBonus>| {
+            AbilityScoreResult::new(
+                Ability::Strength,
+                AbilityScore::of(base),
+                bonuses.into_iter().map(|bonus| (source.clone(), bonus)).collect(),
+            )
+            .score()
+        };
+
+        assert_eq!(score(15, vec![]), AbilityScore::of(15));
+        assert_eq!(
+            score(15, vec![AbilityScoreBonus::Modifier { modifier: 2 }]),
+            AbilityScore::of(17)
+        );
+        assert_eq!(
+            score(
+                8,
+                vec![
+                    AbilityScoreBonus::Become { value: 19 },
+                    AbilityScoreBonus::Modifier { modifier: 2 }
+                ]
+            ),
+            AbilityScore::of(19)
+        );
+        assert_eq!(
+            score(
+                18,
+                vec![
+                    AbilityScoreBonus::Modifier { modifier: 2 },
+                    AbilityScoreBonus::Become { value: 19 }
+                ]
+            ),
+            AbilityScore::of(20)
+        );
+    }
 }
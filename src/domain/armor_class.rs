@@ -0,0 +1,262 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::effect::Effect;
+use crate::render::Render;
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Armor {
+    pub category: ArmorCategory,
+    /// The armor class granted by the armor, or the bonus granted by a shield.
+    pub base: isize,
+    /// Overrides the Dexterity cap of the armor's category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_dexterity: Option<isize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ArmorCategory {
+    Light,
+    Medium,
+    Heavy,
+    Shield,
+}
+
+impl Armor {
+    pub fn is_shield(&self) -> bool {
+        self.category == ArmorCategory::Shield
+    }
+
+    fn max_dexterity(&self) -> Option<isize> {
+        self.max_dexterity.or(match self.category {
+            ArmorCategory::Light => None,
+            ArmorCategory::Medium => Some(2),
+            ArmorCategory::Heavy | ArmorCategory::Shield => Some(0),
+        })
+    }
+}
+
+impl Display for Armor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.category {
+            ArmorCategory::Shield => write!(f, "shield +{}", self.base),
+            _ => write!(
+                f,
+                "{} armor {}",
+                format!("{:?}", self.category).to_lowercase(),
+                self.base
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum ArmorClassBonus {
+    Modifier {
+        modifier: isize,
+    },
+    /// An alternative to 10 + Dexterity while not wearing armor, such as a Barbarian's or Monk's
+    /// Unarmored Defense or Mage Armor.
+    Unarmored {
+        base: isize,
+        abilities: Vec<Ability>,
+        #[serde(default)]
+        without_shield: bool,
+    },
+}
+
+/// Every part of a character's armor class and where it came from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArmorClassResult {
+    components: Vec<(Vec<String>, isize)>,
+}
+
+impl ArmorClassResult {
+    pub fn total(&self) -> isize {
+        self.components.iter().map(|(_, value)| value).sum()
+    }
+
+    pub fn calculate(character: &Character) -> ArmorClassResult {
+        let equipped = character.equipped_armor();
+        let body_armor = equipped.iter().find(|(_, armor)| !armor.is_shield());
+        let shield = equipped.iter().find(|(_, armor)| armor.is_shield());
+        let effects = character
+            .all_effects()
+            .into_iter()
+            .flat_map(|(path, effect)| match effect {
+                Effect::ArmorClass { bonus } => Some((path, bonus)),
+                _ => None,
+            })
+            .collect::<Vec<(Vec<String>, ArmorClassBonus)>>();
+
+        let dexterity = character.get_ability_score(Ability::Dexterity).modifier();
+        let ability = |ability: &Ability| {
+            (
+                vec![ability.to_string()],
+                character.get_ability_score(ability.clone()).modifier(),
+            )
+        };
+
+        // Each candidate is a base armor class and whether a shield can be used with it.
+        let candidates = match body_armor {
+            Some((path, armor)) => {
+                let dexterity = armor
+                    .max_dexterity()
+                    .map_or(dexterity, |max| dexterity.min(max));
+                vec![(
+                    vec![(path.clone(), armor.base), (vec![Ability::Dexterity.to_string()], dexterity)],
+                    true,
+                )]
+            }
+            None => {
+                let mut candidates = vec![(
+                    vec![
+                        (vec!["unarmored".to_string()], 10),
+                        ability(&Ability::Dexterity),
+                    ],
+                    true,
+                )];
+                candidates.extend(effects.iter().flat_map(|(path, bonus)| match bonus {
+                    ArmorClassBonus::Unarmored {
+                        base,
+                        abilities,
+                        without_shield,
+                    } => {
+                        let mut components = vec![(path.clone(), *base)];
+                        components.extend(abilities.iter().map(ability));
+                        Some((components, !without_shield))
+                    }
+                    ArmorClassBonus::Modifier { .. } => None,
+                }));
+                candidates
+            }
+        };
+
+        let mut components = candidates
+            .into_iter()
+            .map(|(mut components, allows_shield)| {
+                if let (Some((path, shield)), true) = (shield, allows_shield) {
+                    components.push((path.clone(), shield.base));
+                }
+                components
+            })
+            .max_by_key(|components| components.iter().map(|(_, value)| value).sum::<isize>())
+            .unwrap_or_default();
+
+        components.extend(effects.into_iter().flat_map(|(path, bonus)| match bonus {
+            ArmorClassBonus::Modifier { modifier } => Some((path, modifier)),
+            ArmorClassBonus::Unarmored { .. } => None,
+        }));
+
+        ArmorClassResult { components }
+    }
+}
+
+impl Render for ArmorClassResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Armor Class\n", <dyn Render>::tab(indent)))?;
+        for (path, value) in &self.components {
+            out.write_fmt(format_args!(
+                "{}{}: {}\n",
+                <dyn Render>::tab(indent + 1),
+                path.iter().join(" / "),
+                value
+            ))?;
+        }
+        out.write_fmt(format_args!(
+            "{}Total: {}\n",
+            <dyn Render>::tab(indent),
+            self.total()
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ArmorClassResult;
+    use crate::domain::character::{test::character, Character};
+    use serde_json::{json, Value};
+
+    /// A character with 18 Dexterity and 16 Constitution wearing `items` and with `effects`.
+    fn wearing(items: Value, effects: Value) -> Character {
+        character(json!({
+            "ability_scores": {
+                "dexterity": { "value": 18 },
+                "constitution": { "value": 16 },
+            },
+            "features": { "Feature": { "effects": effects } },
+            "inventory": { "items": items },
+        }))
+    }
+
+    fn armor(category: &str, base: isize) -> Value {
+        json!({
+            "type": "Object",
+            "count": 1,
+            "armor": { "category": category, "base": base },
+            "equipped": true,
+        })
+    }
+
+    fn unarmored_defense(without_shield: bool) -> Value {
+        json!([{
+            "type": "ArmorClass",
+            "bonus": {
+                "type": "Unarmored",
+                "base": 10,
+                "abilities": ["Dexterity", "Constitution"],
+                "without_shield": without_shield,
+            },
+        }])
+    }
+
+    fn armor_class(character: &Character) -> isize {
+        ArmorClassResult::calculate(character).total()
+    }
+
+    #[test]
+    fn test_dexterity_cap() {
+        assert_eq!(armor_class(&wearing(json!({ "leather": armor("Light", 11) }), json!([]))), 15);
+        assert_eq!(armor_class(&wearing(json!({ "scale": armor("Medium", 14) }), json!([]))), 16);
+        assert_eq!(armor_class(&wearing(json!({ "plate": armor("Heavy", 18) }), json!([]))), 18);
+
+        let mut mail = armor("Medium", 14);
+        mail["armor"]["max_dexterity"] = json!(3);
+        assert_eq!(armor_class(&wearing(json!({ "mail": mail }), json!([]))), 17);
+    }
+
+    #[test]
+    fn test_shield() {
+        let shield = json!({ "shield": armor("Shield", 2) });
+        assert_eq!(armor_class(&wearing(shield.clone(), json!([]))), 16);
+        assert_eq!(armor_class(&wearing(shield.clone(), unarmored_defense(false))), 19);
+        // Without the shield, 10 + Dexterity + Constitution beats 10 + Dexterity + shield.
+        assert_eq!(armor_class(&wearing(shield.clone(), unarmored_defense(true))), 17);
+
+        let mut defense = unarmored_defense(true);
+        defense[0]["bonus"]["abilities"] = json!(["Wisdom"]);
+        assert_eq!(armor_class(&wearing(shield, defense)), 16);
+    }
+
+    #[test]
+    fn test_best_candidate() {
+        let mut effects = unarmored_defense(false);
+        effects.as_array_mut().unwrap().push(json!({
+            "type": "ArmorClass",
+            "bonus": { "type": "Unarmored", "base": 13, "abilities": ["Dexterity"] },
+        }));
+        assert_eq!(armor_class(&wearing(json!({}), effects.clone())), 17);
+
+        // Armor replaces every unarmored candidate, even a better one.
+        assert_eq!(armor_class(&wearing(json!({ "leather": armor("Light", 11) }), effects)), 15);
+
+        let modifier = json!([{ "type": "ArmorClass", "bonus": { "type": "Modifier", "modifier": 1 } }]);
+        assert_eq!(armor_class(&wearing(json!({}), modifier)), 15);
+    }
+}
@@ -1,11 +1,21 @@
 use super::ability_score::AbilityScores;
-use crate::domain::ability_score::{Ability, AbilityScore};
+use crate::domain::ability_score::{Ability, AbilityScore, AbilityScoreResult};
 use crate::domain::effect::Effect;
-use crate::domain::inventory::Inventory;
+use crate::domain::armor_class::Armor;
+use crate::domain::inventory::{Inventory, InventoryItem, ItemDetails};
 use crate::domain::roll::Roll;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use crate::domain::hit_dice::HitDice;
+use crate::domain::condition::{Condition, Conditions};
+use crate::domain::currency::Coins;
+use crate::domain::encumbrance::{EncumbranceResult, Size};
 use crate::domain::hit_points::HitPoints;
+use crate::domain::resource::{Resource, Resources};
+use crate::domain::speed::SpeedResult;
+use crate::domain::spell::Spellbook;
+use crate::domain::spell_slots::{SpellSlots, Spellcasting};
 type FeatureName = String;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
@@ -20,34 +30,296 @@ pub struct Character {
     features: HashMap<FeatureName, Feature>,
     #[serde(default)]
     inventory: Inventory,
+    #[serde(default)]
+    hit_dice: HitDice,
+    #[serde(default)]
+    resources: Resources,
+    #[serde(default)]
+    spell_slots: SpellSlots,
+    #[serde(default)]
+    spellbook: Spellbook,
+    /// The spell currently being concentrated on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    concentration: Option<String>,
+    /// Every condition a character can have, usually defined by the template.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    conditions: HashMap<String, Condition>,
+    #[serde(default, skip_serializing_if = "Conditions::is_empty")]
+    active_conditions: Conditions,
+    /// Details of common items that inventory objects can refer to, usually defined by the
+    /// template.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    catalog: HashMap<String, ItemDetails>,
+    #[serde(default)]
+    size: Size,
+    /// Walking speed in feet, 30 when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    speed: Option<isize>,
+    /// Whether carrying more than 5 and 10 times Strength slows a character down.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    variant_encumbrance: bool,
+    #[serde(default, skip_serializing_if = "Coins::is_empty")]
+    wallet: Coins,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct CharacterClass {
     name: String,
     level: isize,
+    /// The number of sides of the class's hit die, by default derived from the class name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hit_die: Option<isize>,
+    /// How the class contributes to spell slots, by default derived from the class name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spellcasting: Option<Spellcasting>,
+    /// The ability used to cast the class's spells, by default derived from the class name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spellcasting_ability: Option<Ability>,
+}
+
+impl CharacterClass {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn level(&self) -> isize {
+        self.level
+    }
+
+    pub fn spellcasting(&self) -> Spellcasting {
+        self.spellcasting
+            .clone()
+            .unwrap_or_else(|| match self.name.to_lowercase().as_str() {
+                "bard" | "cleric" | "druid" | "sorcerer" | "wizard" => Spellcasting::Full,
+                "paladin" | "ranger" => Spellcasting::Half,
+                "artificer" => Spellcasting::HalfRoundedUp,
+                "warlock" => Spellcasting::Pact,
+                _ => Spellcasting::None,
+            })
+    }
+
+    pub fn spellcasting_ability(&self) -> Option<Ability> {
+        self.spellcasting_ability
+            .clone()
+            .or_else(|| match self.name.to_lowercase().as_str() {
+                "artificer" | "wizard" => Some(Ability::Intelligence),
+                "cleric" | "druid" | "ranger" => Some(Ability::Wisdom),
+                "bard" | "paladin" | "sorcerer" | "warlock" => Some(Ability::Charisma),
+                _ => None,
+            })
+    }
+
+    pub fn hit_die(&self) -> isize {
+        self.hit_die
+            .unwrap_or_else(|| match self.name.to_lowercase().as_str() {
+                "barbarian" => 12,
+                "fighter" | "paladin" | "ranger" => 10,
+                "sorcerer" | "wizard" => 6,
+                _ => 8,
+            })
+    }
 }
 
 impl Character {
+    /// Every effect, including those of being encumbered.
     pub fn all_effects(&self) -> Vec<(Vec<String>, Effect)> {
-        self.features
+        let mut effects = self.base_effects();
+        effects.extend(self.encumbrance().effects());
+        effects
+    }
+
+    /// Every effect of features, conditions and equipped items. Encumbrance depends on Strength,
+    /// so ability scores only use these.
+    fn base_effects(&self) -> Vec<(Vec<String>, Effect)> {
+        let mut effects: Vec<(Vec<String>, Effect)> = self
+            .features
             .iter()
             .flat_map(|(name, feature)| feature.all_effects(vec![name.clone()]))
+            .collect();
+
+        effects.extend(self.active_conditions.active().iter().flat_map(|(name, level)| {
+            self.conditions
+                .get(name)
+                .map(|condition| condition.effects(vec![name.clone()], *level))
+                .unwrap_or_default()
+        }));
+
+        effects.extend(self.inventory.equipped().into_iter().flat_map(|(path, item)| match item {
+            InventoryItem::Object { effects, .. } => effects
+                .iter()
+                .map(|effect| (path.clone(), effect.clone()))
+                .collect(),
+            InventoryItem::Container { .. } => vec![],
+        }));
+
+        effects
+    }
+
+    /// Every resource declared by a feature, with the path of the feature and its name.
+    pub fn all_resources(&self) -> Vec<(Vec<String>, String, Resource)> {
+        self.features
+            .iter()
+            .flat_map(|(name, feature)| feature.all_resources(vec![name.clone()]))
+            .collect()
+    }
+
+    pub fn equipped_armor(&self) -> Vec<(Vec<String>, Armor)> {
+        self.inventory
+            .equipped()
+            .into_iter()
+            .flat_map(|(path, item)| match item {
+                InventoryItem::Object {
+                    armor: Some(armor), ..
+                } => Some((path, armor.clone())),
+                _ => None,
+            })
             .collect()
     }
 
     pub fn get_ability_score(&self, ability: Ability) -> AbilityScore {
-        self.ability_scores.get(ability)
+        self.ability_score_result(ability).score()
+    }
+
+    /// The effective score of an ability: the base score with every ability score effect applied.
+    pub fn ability_score_result(&self, ability: Ability) -> AbilityScoreResult {
+        let bonuses = self
+            .base_effects()
+            .into_iter()
+            .flat_map(|(path, effect)| match effect {
+                Effect::AbilityScore {
+                    ability: affected,
+                    bonus,
+                } if affected == ability => Some((path, bonus)),
+                _ => None,
+            })
+            .collect();
+
+        AbilityScoreResult::new(ability.clone(), self.ability_scores.get(ability), bonuses)
     }
 
     pub fn inventory(&mut self) -> &mut Inventory {
         &mut self.inventory
     }
 
+    pub fn encumbrance(&self) -> EncumbranceResult {
+        EncumbranceResult::new(
+            self.inventory_with_catalog().total_weight() + self.wallet.weight(),
+            self.get_ability_score(Ability::Strength).value(),
+            self.size.clone(),
+            self.variant_encumbrance,
+        )
+    }
+
+    pub fn speed(&self) -> SpeedResult {
+        let modifiers = self
+            .all_effects()
+            .into_iter()
+            .flat_map(|(path, effect)| match effect {
+                Effect::Speed { bonus } => Some((path, bonus)),
+                _ => None,
+            })
+            .collect();
+        SpeedResult::new(self.speed.unwrap_or(30), modifiers)
+    }
+
+    /// The inventory with details filled in from the catalog, such as for showing weights.
+    pub fn inventory_with_catalog(&self) -> Inventory {
+        self.inventory.with_catalog(&self.catalog)
+    }
+
+    /// Hit points with effects applied as of when the character was loaded or its conditions last
+    /// changed.
     pub fn hit_points(&mut self) -> &mut HitPoints {
         &mut self.hit_points
     }
 
+    /// Applies effects such as exhaustion to hit points. Needed whenever the effects change.
+    pub fn apply_hit_point_effects(&mut self) {
+        let effects = self.all_effects();
+        self.hit_points.apply_effects(&effects);
+    }
+
+    pub fn hit_dice(&mut self) -> &mut HitDice {
+        &mut self.hit_dice
+    }
+
+    pub fn wallet(&mut self) -> &mut Coins {
+        &mut self.wallet
+    }
+
+    pub fn classes(&self) -> &[CharacterClass] {
+        &self.classes
+    }
+
+    /// The ability used to cast spells of the given class, or of the first class that has one.
+    pub fn spellcasting_ability(&self, class: Option<&str>) -> Option<Ability> {
+        self.classes
+            .iter()
+            .filter(|candidate| class.is_none_or(|class| candidate.name.eq_ignore_ascii_case(class)))
+            .find_map(|class| class.spellcasting_ability())
+    }
+
+    pub fn condition(&self, name: &str) -> Result<Condition> {
+        self.conditions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No condition named {}", name))
+    }
+
+    pub fn active_conditions(&self) -> &Conditions {
+        &self.active_conditions
+    }
+
+    /// Adds a condition, or another level of it, and applies its effects on hit points. Returns the
+    /// new level.
+    pub fn add_condition(&mut self, name: &str, level: Option<isize>) -> Result<isize> {
+        let condition = self.condition(name)?;
+        let level = self.active_conditions.add(name, &condition, level)?;
+        self.apply_hit_point_effects();
+        Ok(level)
+    }
+
+    /// Removes a condition, or a level of it, and its effects on hit points. Returns the new level.
+    pub fn remove_condition(&mut self, name: &str) -> Result<isize> {
+        let condition = self.condition(name)?;
+        let level = self.active_conditions.remove(name, &condition)?;
+        self.apply_hit_point_effects();
+        Ok(level)
+    }
+
+    pub fn concentration(&self) -> Option<&String> {
+        self.concentration.as_ref()
+    }
+
+    /// Starts or ends concentration, returning the spell previously concentrated on.
+    pub fn set_concentration(&mut self, spell: Option<String>) -> Option<String> {
+        std::mem::replace(&mut self.concentration, spell)
+    }
+
+    pub fn spellbook(&mut self) -> &mut Spellbook {
+        &mut self.spellbook
+    }
+
+    pub fn spell_slots(&mut self) -> &mut SpellSlots {
+        &mut self.spell_slots
+    }
+
+    pub fn resources(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    pub fn resources_used(&self, name: &str) -> isize {
+        self.resources.used(name)
+    }
+
+    /// The hit dice granted by every class, by number of sides.
+    pub fn total_hit_dice(&self) -> BTreeMap<isize, isize> {
+        self.classes.iter().fold(BTreeMap::new(), |mut total, class| {
+            *total.entry(class.hit_die()).or_insert(0) += class.level;
+            total
+        })
+    }
+
     pub fn with_inventory(&self, inventory: Inventory) -> Character {
         Character {
             inventory,
@@ -55,12 +327,62 @@ impl Character {
         }
     }
 
+    pub fn with_wallet(&self, wallet: Coins) -> Character {
+        Character {
+            wallet,
+            ..self.clone()
+        }
+    }
+
     pub fn with_hit_points(&self, hit_points: HitPoints) -> Character {
         Character {
             hit_points,
             ..self.clone()
         }
     }
+
+    pub fn with_hit_dice(&self, hit_dice: HitDice) -> Character {
+        Character {
+            hit_dice,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_resources(&self, resources: Resources) -> Character {
+        Character {
+            resources,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_spell_slots(&self, spell_slots: SpellSlots) -> Character {
+        Character {
+            spell_slots,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_spellbook(&self, spellbook: Spellbook) -> Character {
+        Character {
+            spellbook,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_concentration(&self, concentration: Option<String>) -> Character {
+        Character {
+            concentration,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_active_conditions(&self, active_conditions: Conditions) -> Character {
+        Character {
+            active_conditions,
+            ..self.clone()
+        }
+    }
+
     pub fn get_feature(&self, name: &String) -> Option<&Feature> {
         self.features.get(name)
     }
@@ -69,6 +391,14 @@ impl Character {
         self.classes.iter().fold(0, |acc, class| acc + class.level)
     }
 
+    pub fn class_level(&self, name: &str) -> isize {
+        self.classes
+            .iter()
+            .filter(|class| class.name.eq_ignore_ascii_case(name))
+            .map(|class| class.level)
+            .sum()
+    }
+
     pub fn proficiency_bonus(&self) -> isize {
         let total_level = self.total_level();
         if total_level < 5 {
@@ -95,6 +425,8 @@ pub struct Feature {
     pub roll: Option<Roll>,
     #[serde(default)]
     pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub resources: HashMap<String, Resource>,
 }
 
 impl Feature {
@@ -113,4 +445,51 @@ impl Feature {
 
         effects
     }
+
+    pub fn all_resources(&self, path: Vec<String>) -> Vec<(Vec<String>, String, Resource)> {
+        let mut resources: Vec<(Vec<String>, String, Resource)> = self
+            .resources
+            .iter()
+            .map(|(name, resource)| (path.clone(), name.clone(), resource.clone()))
+            .collect();
+
+        resources.extend(self.children.iter().flat_map(|(name, feature)| {
+            let mut path = path.clone();
+            path.push(name.clone());
+            feature.all_resources(path)
+        }));
+
+        resources
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Character;
+    use serde_json::{json, Value};
+
+    /// A character with 10 in every ability score. `overrides` replaces any other field, and any
+    /// ability score given in its `ability_scores`.
+    pub fn character(overrides: Value) -> Character {
+        let mut character = json!({
+            "ability_scores": {
+                "strength": { "value": 10 },
+                "dexterity": { "value": 10 },
+                "constitution": { "value": 10 },
+                "intelligence": { "value": 10 },
+                "wisdom": { "value": 10 },
+                "charisma": { "value": 10 },
+            },
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            match (key.as_str(), value) {
+                ("ability_scores", Value::Object(scores)) => character["ability_scores"]
+                    .as_object_mut()
+                    .unwrap()
+                    .extend(scores.clone()),
+                _ => character[key] = value.clone(),
+            }
+        }
+        serde_json::from_value(character).unwrap()
+    }
 }
@@ -0,0 +1,78 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::roll::{Roll, RollContext, RollResult};
+use crate::render::Render;
+use anyhow::Result;
+use std::cmp::max;
+use std::io::Write;
+
+/// The Constitution saving throw to keep concentrating on a spell after taking damage.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConcentrationCheck {
+    spell: String,
+    dc: isize,
+    /// Not rolled when the damage left the caster unconscious, which always breaks concentration.
+    roll: Option<RollResult>,
+    maintained: bool,
+}
+
+impl ConcentrationCheck {
+    /// Rolls to maintain concentration after taking `damage`, against a DC of half the damage but
+    /// at least 10, and ends concentration on a failure.
+    pub fn after_damage(character: &mut Character, damage: isize) -> Option<ConcentrationCheck> {
+        let spell = character.concentration().cloned()?;
+        if damage <= 0 {
+            return None;
+        }
+        let dc = max(10, damage / 2);
+        let roll = if character.hit_points().is_conscious() {
+            let context = RollContext {
+                ability: Some(Ability::Constitution),
+                ..RollContext::default()
+            };
+            Some(Roll::calculate(
+                &vec![
+                    "saving-throw".to_string(),
+                    "constitution".to_string(),
+                    "concentration".to_string(),
+                ],
+                &context,
+                character,
+            ))
+        } else {
+            None
+        };
+        let maintained = roll.as_ref().is_some_and(|roll| roll.total() >= dc);
+        if !maintained {
+            character.set_concentration(None);
+        }
+
+        Some(ConcentrationCheck {
+            spell,
+            dc,
+            roll,
+            maintained,
+        })
+    }
+}
+
+impl Render for ConcentrationCheck {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!(
+            "{}Concentration on {} (DC {})\n",
+            <dyn Render>::tab(indent),
+            self.spell,
+            self.dc
+        ))?;
+        match &self.roll {
+            Some(roll) => roll.render(indent + 1, out)?,
+            None => out.write_fmt(format_args!("{}Unconscious\n", <dyn Render>::tab(indent + 1)))?,
+        }
+        out.write_fmt(format_args!(
+            "{}{}\n",
+            <dyn Render>::tab(indent + 1),
+            if self.maintained { "Maintained" } else { "Broken" }
+        ))?;
+        Ok(())
+    }
+}
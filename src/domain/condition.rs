@@ -0,0 +1,145 @@
+use crate::domain::effect::Effect;
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// A condition as defined in the template, such as poisoned, or a leveled condition such as
+/// exhaustion.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct Condition {
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// The additional effects of each level of a leveled condition, which are cumulative.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub levels: Vec<Vec<Effect>>,
+    /// Loses one level, or ends, at the end of a long rest.
+    #[serde(default)]
+    pub long_rest_recovery: bool,
+}
+
+impl Condition {
+    fn max_level(&self) -> isize {
+        max(1, self.levels.len() as isize)
+    }
+
+    pub fn is_leveled(&self) -> bool {
+        !self.levels.is_empty()
+    }
+
+    pub fn effects(&self, path: Vec<String>, level: isize) -> Vec<(Vec<String>, Effect)> {
+        let mut effects: Vec<(Vec<String>, Effect)> = self
+            .effects
+            .iter()
+            .map(|effect| (path.clone(), effect.clone()))
+            .collect();
+
+        effects.extend(
+            self.levels
+                .iter()
+                .take(max(0, level) as usize)
+                .enumerate()
+                .flat_map(|(index, level)| {
+                    let mut path = path.clone();
+                    path.push(format!("level {}", index + 1));
+                    level.iter().map(move |effect| (path.clone(), effect.clone()))
+                }),
+        );
+
+        effects
+    }
+}
+
+/// The conditions a character currently has, with their level.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Conditions {
+    active: BTreeMap<String, isize>,
+}
+
+impl Conditions {
+    pub fn active(&self) -> &BTreeMap<String, isize> {
+        &self.active
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Adds the condition, or another level of a leveled condition unless `level` is given.
+    /// Returns the new level.
+    pub fn add(&mut self, name: &str, condition: &Condition, level: Option<isize>) -> Result<isize> {
+        let current = self.active.get(name).copied().unwrap_or(0);
+        let level = level.unwrap_or(if condition.is_leveled() { current + 1 } else { 1 });
+        if level < 1 || level > condition.max_level() {
+            return Err(anyhow!(
+                "{} can be at most level {}",
+                name,
+                condition.max_level()
+            ));
+        }
+        self.active.insert(name.to_string(), level);
+        Ok(level)
+    }
+
+    /// Removes the condition, or a single level of a leveled condition. Returns the remaining
+    /// level.
+    pub fn remove(&mut self, name: &str, condition: &Condition) -> Result<isize> {
+        let current = self
+            .active
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("Not {}", name))?;
+        let level = if condition.is_leveled() { current - 1 } else { 0 };
+        if level > 0 {
+            self.active.insert(name.to_string(), min(level, condition.max_level()));
+        } else {
+            self.active.remove(name);
+        }
+        Ok(max(0, level))
+    }
+}
+
+impl Render for Conditions {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Conditions\n", <dyn Render>::tab(indent)))?;
+        if self.active.is_empty() {
+            out.write_fmt(format_args!("{}None\n", <dyn Render>::tab(indent + 1)))?;
+        }
+        for (name, level) in &self.active {
+            out.write_fmt(format_args!("{}{}", <dyn Render>::tab(indent + 1), name))?;
+            if *level > 1 {
+                out.write_fmt(format_args!(" (level {})", level))?;
+            }
+            out.write_fmt(format_args!("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Condition, Conditions};
+
+    #[test]
+    fn test_levels() {
+        let exhaustion = Condition {
+            levels: vec![vec![]; 6],
+            ..Condition::default()
+        };
+        let poisoned = Condition::default();
+        let mut conditions = Conditions::default();
+        assert_eq!(conditions.add("exhaustion", &exhaustion, None).unwrap(), 1);
+        assert_eq!(conditions.add("exhaustion", &exhaustion, None).unwrap(), 2);
+        assert!(conditions.add("exhaustion", &exhaustion, Some(7)).is_err());
+        assert_eq!(conditions.add("poisoned", &poisoned, None).unwrap(), 1);
+        assert_eq!(conditions.add("poisoned", &poisoned, None).unwrap(), 1);
+
+        assert_eq!(conditions.remove("exhaustion", &exhaustion).unwrap(), 1);
+        assert_eq!(conditions.remove("poisoned", &poisoned).unwrap(), 0);
+        assert!(conditions.remove("poisoned", &poisoned).is_err());
+        assert_eq!(conditions.active().get("exhaustion"), Some(&1));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::domain::inventory::Weight;
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Coin {
+    Copper,
+    Silver,
+    Electrum,
+    Gold,
+    Platinum,
+}
+
+impl Coin {
+    /// From least to most valuable.
+    pub const ALL: [Coin; 5] = [Coin::Copper, Coin::Silver, Coin::Electrum, Coin::Gold, Coin::Platinum];
+
+    /// The value in copper.
+    pub fn value(&self) -> isize {
+        match self {
+            Coin::Copper => 1,
+            Coin::Silver => 10,
+            Coin::Electrum => 50,
+            Coin::Gold => 100,
+            Coin::Platinum => 1000,
+        }
+    }
+
+    pub fn of(abbreviation: &str) -> Option<Coin> {
+        Coin::ALL
+            .iter()
+            .copied()
+            .find(|coin| coin.to_string() == abbreviation.to_lowercase())
+    }
+}
+
+impl Display for Coin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Coin::Copper => write!(f, "cp"),
+            Coin::Silver => write!(f, "sp"),
+            Coin::Electrum => write!(f, "ep"),
+            Coin::Gold => write!(f, "gp"),
+            Coin::Platinum => write!(f, "pp"),
+        }
+    }
+}
+
+fn is_zero(value: &isize) -> bool {
+    *value == 0
+}
+
+/// An amount of money in each kind of coin, such as the cost of an item.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct Coins {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cp: isize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub sp: isize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub ep: isize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub gp: isize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pp: isize,
+}
+
+impl Coins {
+    /// Parses amounts of coins separated by spaces, such as `5gp 3 sp`.
+    pub fn parse(value: &str) -> Result<Coins> {
+        let mut coins = Coins::default();
+        let mut words = value.split_whitespace();
+        while let Some(word) = words.next() {
+            let digits = word.find(|c: char| !c.is_ascii_digit()).unwrap_or(word.len());
+            let (count, coin) = word.split_at(digits);
+            let count = count
+                .parse::<isize>()
+                .map_err(|_| anyhow!("Expected an amount of coins but found '{}'", word))?;
+            let coin = match coin {
+                "" => words
+                    .next()
+                    .ok_or_else(|| anyhow!("Expected a kind of coin after {}", count))?,
+                coin => coin,
+            };
+            let coin = Coin::of(coin).ok_or_else(|| anyhow!("Unknown kind of coin '{}'", coin))?;
+            *coins.count_mut(coin) += count;
+        }
+        Ok(coins)
+    }
+
+    pub fn of(coin: Coin, count: isize) -> Coins {
+        let mut coins = Coins::default();
+        *coins.count_mut(coin) = count;
+        coins
+    }
+
+    pub fn count(&self, coin: Coin) -> isize {
+        match coin {
+            Coin::Copper => self.cp,
+            Coin::Silver => self.sp,
+            Coin::Electrum => self.ep,
+            Coin::Gold => self.gp,
+            Coin::Platinum => self.pp,
+        }
+    }
+
+    fn count_mut(&mut self, coin: Coin) -> &mut isize {
+        match coin {
+            Coin::Copper => &mut self.cp,
+            Coin::Silver => &mut self.sp,
+            Coin::Electrum => &mut self.ep,
+            Coin::Gold => &mut self.gp,
+            Coin::Platinum => &mut self.pp,
+        }
+    }
+
+    pub fn plus(&self, other: &Coins) -> Coins {
+        let mut coins = self.clone();
+        for coin in Coin::ALL {
+            *coins.count_mut(coin) += other.count(coin);
+        }
+        coins
+    }
+
+    fn minus(&self, other: &Coins) -> Coins {
+        let mut coins = self.clone();
+        for coin in Coin::ALL {
+            *coins.count_mut(coin) -= other.count(coin);
+        }
+        coins
+    }
+
+    /// Fifty coins of any kind weigh a pound.
+    pub fn weight(&self) -> Weight {
+        Weight::from_hundredths(Coin::ALL.iter().map(|coin| self.count(*coin)).sum::<isize>() * 2)
+    }
+
+    /// Pays `cost` with the coins asked for where possible, then with smaller coins before larger
+    /// ones. When that is not exact, the smallest coin worth the rest is broken instead and the
+    /// change is received in gold, silver and copper.
+    pub fn spend(&mut self, cost: &Coins) -> Result<Transaction> {
+        if cost.in_copper() > self.in_copper() {
+            return Err(anyhow!("Cannot spend {}, only have {}", cost, self));
+        }
+        let mut paid = Coins::default();
+        for coin in Coin::ALL {
+            *paid.count_mut(coin) = min(self.count(coin), cost.count(coin));
+        }
+        let remaining = cost.in_copper() - paid.in_copper();
+        let unpaid = |paid: &Coins, value: isize| {
+            Coin::ALL
+                .iter()
+                .copied()
+                .find(|coin| self.count(*coin) > paid.count(*coin) && coin.value() >= value)
+        };
+
+        let mut smaller = paid.clone();
+        let mut left = remaining;
+        for coin in Coin::ALL {
+            let count = min(self.count(coin) - smaller.count(coin), left / coin.value());
+            *smaller.count_mut(coin) += count;
+            left -= count * coin.value();
+        }
+        let (broken, left) = match (left, unpaid(&paid, remaining)) {
+            (0, _) => (None, 0),
+            (_, Some(coin)) => (Some(coin), remaining),
+            // No single coin is worth the rest, and every coin left is worth more than what the
+            // smaller coins couldn't pay.
+            _ => {
+                paid = smaller.clone();
+                (unpaid(&smaller, left), left)
+            }
+        };
+        let change = match broken {
+            Some(coin) => {
+                *paid.count_mut(coin) += 1;
+                Coins::from_copper(coin.value() - left)
+            }
+            None => {
+                paid = smaller;
+                Coins::default()
+            }
+        };
+        *self = self.minus(&paid).plus(&change);
+        Ok(Transaction::Spend {
+            cost: cost.clone(),
+            paid,
+            change,
+        })
+    }
+
+    /// Exchanges `given`, which must all be in the wallet, for as many coins of the kind `to` as
+    /// they are worth. What is left over is received in gold, silver and copper.
+    pub fn convert(&mut self, given: &Coins, to: Coin) -> Result<Transaction> {
+        if Coin::ALL.iter().any(|coin| given.count(*coin) > self.count(*coin)) {
+            return Err(anyhow!("Cannot convert {}, only have {}", given, self));
+        }
+        let value = given.in_copper();
+        if value < to.value() {
+            return Err(anyhow!("{} is not enough for a single {}", given, to));
+        }
+        let received = Coins::of(to, value / to.value()).plus(&Coins::from_copper(value % to.value()));
+        *self = self.minus(given).plus(&received);
+        Ok(Transaction::Convert {
+            given: given.clone(),
+            received,
+        })
+    }
+
+    /// Divides the coins into `shares` equal shares. Coins that can't be divided evenly are
+    /// exchanged for smaller ones, down to copper pieces which are left over.
+    pub fn split(&self, shares: isize) -> Result<Transaction> {
+        if shares < 1 {
+            return Err(anyhow!("Cannot split into {} shares", shares));
+        }
+        let mut share = Coins::default();
+        let (mut gold, mut silver, mut copper) = (self.gp, self.sp, self.cp);
+        share.pp = self.pp / shares;
+        gold += self.pp % shares * 10;
+        share.gp = gold / shares;
+        silver += gold % shares * 10;
+        share.ep = self.ep / shares;
+        silver += self.ep % shares * 5;
+        share.sp = silver / shares;
+        copper += silver % shares * 10;
+        share.cp = copper / shares;
+        Ok(Transaction::Split {
+            loot: self.clone(),
+            shares,
+            share,
+            left_over: Coins::from_copper(copper % shares),
+        })
+    }
+
+    /// The amount in gold, silver and copper, the way prices are written.
+    pub fn from_copper(copper: isize) -> Coins {
+        Coins {
+            gp: copper / 100,
+            sp: copper % 100 / 10,
+            cp: copper % 10,
+            ..Coins::default()
+        }
+    }
+
+    pub fn in_copper(&self) -> isize {
+        self.cp + self.sp * 10 + self.ep * 50 + self.gp * 100 + self.pp * 1000
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.in_copper() == 0
+    }
+}
+
+impl Display for Coins {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let coins = [
+            (self.pp, "pp"),
+            (self.gp, "gp"),
+            (self.ep, "ep"),
+            (self.sp, "sp"),
+            (self.cp, "cp"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count != 0)
+        .map(|(count, coin)| format!("{} {}", count, coin))
+        .collect::<Vec<String>>();
+        if coins.is_empty() {
+            write!(f, "0 cp")
+        } else {
+            write!(f, "{}", coins.join(" "))
+        }
+    }
+}
+
+/// A change to the coins in a wallet.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Transaction {
+    Add { coins: Coins },
+    Spend { cost: Coins, paid: Coins, change: Coins },
+    Convert { given: Coins, received: Coins },
+    /// Loot divided among a party, one share of which may be taken.
+    Split {
+        loot: Coins,
+        shares: isize,
+        share: Coins,
+        left_over: Coins,
+    },
+}
+
+/// The coins in a wallet, after a transaction if there was one.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WalletResult {
+    pub transaction: Option<Transaction>,
+    pub wallet: Coins,
+}
+
+impl Render for Transaction {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let tab = <dyn Render>::tab(indent);
+        match self {
+            Transaction::Add { coins } => {
+                out.write_fmt(format_args!("{}Added {}\n", tab, coins))?;
+            }
+            Transaction::Spend { cost, paid, change } => {
+                out.write_fmt(format_args!("{}Spent {}", tab, cost))?;
+                if paid != cost {
+                    out.write_fmt(format_args!(", paying {}", paid))?;
+                }
+                if !change.is_empty() {
+                    out.write_fmt(format_args!(" and receiving {} in change", change))?;
+                }
+                out.write_fmt(format_args!("\n"))?;
+            }
+            Transaction::Convert { given, received } => {
+                out.write_fmt(format_args!("{}Converted {} into {}\n", tab, given, received))?;
+            }
+            Transaction::Split {
+                loot,
+                shares,
+                share,
+                left_over,
+            } => {
+                out.write_fmt(format_args!("{}Split {} into {} shares of {}\n", tab, loot, shares, share))?;
+                if !left_over.is_empty() {
+                    out.write_fmt(format_args!("{}{} left over\n", <dyn Render>::tab(indent + 1), left_over))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Render for WalletResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        if let Some(transaction) = &self.transaction {
+            transaction.render(indent, out)?;
+        }
+        out.write_fmt(format_args!(
+            "{}Wallet: {} (worth {})\n",
+            <dyn Render>::tab(indent),
+            self.wallet,
+            Coins::from_copper(self.wallet.in_copper())
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Coin, Coins, Transaction};
+
+    #[test]
+    fn test_spend() {
+        let mut wallet = Coins::parse("1pp 2 gp 5sp").unwrap();
+        assert_eq!(wallet.spend(&Coins::parse("3 gp").unwrap()).unwrap(), Transaction::Spend {
+            cost: Coins::of(Coin::Gold, 3),
+            paid: Coins::parse("2 gp 1 pp").unwrap(),
+            change: Coins::of(Coin::Gold, 9),
+        });
+        assert_eq!(wallet, Coins::parse("9 gp 5 sp").unwrap());
+        assert!(wallet.spend(&Coins::of(Coin::Gold, 10)).is_err());
+        assert_eq!(wallet.spend(&Coins::parse("9 gp 2 sp 5 cp").unwrap()).unwrap(), Transaction::Spend {
+            cost: Coins::parse("9 gp 2 sp 5 cp").unwrap(),
+            paid: Coins::parse("9 gp 3 sp").unwrap(),
+            change: Coins::of(Coin::Copper, 5),
+        });
+        assert_eq!(wallet, Coins::parse("2 sp 5 cp").unwrap());
+    }
+
+    #[test]
+    fn test_split() {
+        let loot = Coins::parse("1 pp 3 gp 1 ep 4 cp").unwrap();
+        assert_eq!(loot.split(4).unwrap(), Transaction::Split {
+            loot: loot.clone(),
+            shares: 4,
+            share: Coins::parse("3 gp 3 sp 8 cp").unwrap(),
+            left_over: Coins::of(Coin::Copper, 2),
+        });
+    }
+}
@@ -0,0 +1,252 @@
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum DamageType {
+    Acid,
+    Bludgeoning,
+    Cold,
+    Fire,
+    Force,
+    Lightning,
+    Necrotic,
+    Piercing,
+    Poison,
+    Psychic,
+    Radiant,
+    Slashing,
+    Thunder,
+}
+
+impl Display for DamageType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl DamageType {
+    pub fn of(value: &str) -> Option<DamageType> {
+        match value.to_lowercase().as_str() {
+            "acid" => Some(DamageType::Acid),
+            "bludgeoning" => Some(DamageType::Bludgeoning),
+            "cold" => Some(DamageType::Cold),
+            "fire" => Some(DamageType::Fire),
+            "force" => Some(DamageType::Force),
+            "lightning" => Some(DamageType::Lightning),
+            "necrotic" => Some(DamageType::Necrotic),
+            "piercing" => Some(DamageType::Piercing),
+            "poison" => Some(DamageType::Poison),
+            "psychic" => Some(DamageType::Psychic),
+            "radiant" => Some(DamageType::Radiant),
+            "slashing" => Some(DamageType::Slashing),
+            "thunder" => Some(DamageType::Thunder),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum DamageAdjustment {
+    /// Halves damage of the given types, rounding down.
+    Resistance,
+    /// Doubles damage of the given types.
+    Vulnerability,
+    /// Prevents all damage of the given types.
+    Immunity,
+}
+
+impl Display for DamageAdjustment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A single instance of damage, such as the `3 fire` in `8 slashing + 3 fire`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Damage {
+    pub amount: isize,
+    pub damage_type: Option<DamageType>,
+}
+
+impl Damage {
+    /// Parses instances of damage separated by `+`, each an amount optionally followed by a
+    /// damage type. Amounts without a type use `default_type`.
+    pub fn parse(value: &str, default_type: Option<DamageType>) -> Result<Vec<Damage>> {
+        value
+            .split('+')
+            .map(|instance| {
+                let mut words = instance.split_whitespace();
+                let amount = words
+                    .next()
+                    .ok_or_else(|| anyhow!("Expected an amount of damage in '{}'", value))?;
+                let amount = amount
+                    .parse::<isize>()
+                    .map_err(|_| anyhow!("Expected an amount of damage but found '{}'", amount))?;
+                let damage_type = match words.next() {
+                    Some(damage_type) => Some(
+                        DamageType::of(damage_type)
+                            .ok_or_else(|| anyhow!("Unknown damage type '{}'", damage_type))?,
+                    ),
+                    None => default_type.clone(),
+                };
+                match words.next() {
+                    Some(extra) => Err(anyhow!("Unexpected '{}' in '{}'", extra, instance.trim())),
+                    None => Ok(Damage {
+                        amount,
+                        damage_type,
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Display for Damage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.damage_type {
+            Some(damage_type) => write!(f, "{} {}", self.amount, damage_type),
+            None => write!(f, "{}", self.amount),
+        }
+    }
+}
+
+/// An instance of damage after resistance, vulnerability and immunity were applied.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AdjustedDamage {
+    damage: Damage,
+    adjustments: Vec<(Vec<String>, DamageAdjustment)>,
+    taken: isize,
+}
+
+impl AdjustedDamage {
+    /// Immunity prevents the damage entirely, otherwise resistance is applied before
+    /// vulnerability. Several sources of the same adjustment don't stack.
+    pub fn new(damage: Damage, adjustments: Vec<(Vec<String>, DamageAdjustment)>) -> AdjustedDamage {
+        let has = |kind: DamageAdjustment| adjustments.iter().any(|(_, adjustment)| adjustment == &kind);
+        let mut taken = damage.amount.max(0);
+        if has(DamageAdjustment::Immunity) {
+            taken = 0;
+        }
+        if has(DamageAdjustment::Resistance) {
+            taken /= 2;
+        }
+        if has(DamageAdjustment::Vulnerability) {
+            taken *= 2;
+        }
+
+        AdjustedDamage {
+            damage,
+            adjustments,
+            taken,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DamageResult {
+    instances: Vec<AdjustedDamage>,
+}
+
+impl DamageResult {
+    pub fn new(instances: Vec<AdjustedDamage>) -> DamageResult {
+        DamageResult { instances }
+    }
+
+    pub fn total(&self) -> isize {
+        self.instances.iter().map(|instance| instance.taken).sum()
+    }
+}
+
+impl Render for DamageResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Damage\n", <dyn Render>::tab(indent)))?;
+        for instance in &self.instances {
+            if instance.adjustments.is_empty() {
+                out.write_fmt(format_args!(
+                    "{}{}\n",
+                    <dyn Render>::tab(indent + 1),
+                    instance.damage
+                ))?;
+            } else {
+                out.write_fmt(format_args!(
+                    "{}{} → {} ({})\n",
+                    <dyn Render>::tab(indent + 1),
+                    instance.damage,
+                    instance.taken,
+                    instance
+                        .adjustments
+                        .iter()
+                        .map(|(path, adjustment)| format!("{} from {}", adjustment, path.iter().join(" / ")))
+                        .join(", ")
+                ))?;
+            }
+        }
+        out.write_fmt(format_args!(
+            "{}Total: {}\n",
+            <dyn Render>::tab(indent),
+            self.total()
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AdjustedDamage, Damage, DamageAdjustment, DamageType};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Damage::parse("8 slashing + 3 fire", None).unwrap(),
+            vec![
+                Damage {
+                    amount: 8,
+                    damage_type: Some(DamageType::Slashing)
+                },
+                Damage {
+                    amount: 3,
+                    damage_type: Some(DamageType::Fire)
+                }
+            ]
+        );
+        assert_eq!(
+            Damage::parse("14", Some(DamageType::Fire)).unwrap(),
+            vec![Damage {
+                amount: 14,
+                damage_type: Some(DamageType::Fire)
+            }]
+        );
+        assert!(Damage::parse("8 slashing +", None).is_err());
+        assert!(Damage::parse("8 sharp", None).is_err());
+    }
+
+    #[test]
+    fn test_adjustments() {
+        let taken = |amount, adjustments: Vec<DamageAdjustment>| {
+            AdjustedDamage::new(
+                Damage {
+                    amount,
+                    damage_type: Some(DamageType::Fire),
+                },
+                adjustments.into_iter().map(|adjustment| (vec![], adjustment)).collect(),
+            )
+            .taken
+        };
+        assert_eq!(taken(7, vec![]), 7);
+        assert_eq!(taken(7, vec![DamageAdjustment::Resistance]), 3);
+        assert_eq!(
+            taken(7, vec![DamageAdjustment::Resistance, DamageAdjustment::Resistance]),
+            3
+        );
+        assert_eq!(taken(7, vec![DamageAdjustment::Vulnerability]), 14);
+        assert_eq!(
+            taken(7, vec![DamageAdjustment::Vulnerability, DamageAdjustment::Resistance]),
+            6
+        );
+        assert_eq!(taken(7, vec![DamageAdjustment::Immunity]), 0);
+    }
+}
@@ -1,32 +1,125 @@
 use crate::domain::ability_score::Ability;
-use crate::domain::roll::Roll;
+use crate::domain::armor_class::ArmorClassBonus;
+use crate::domain::damage::{DamageAdjustment, DamageType};
+use crate::domain::hit_points::HitPointMaximumBonus;
+use crate::domain::roll::{Roll, RollContext};
+use crate::domain::speed::SpeedBonus;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum Effect {
     Roll { bonus: RollBonus, scope: RollScope },
+    AbilityScore { ability: Ability, bonus: AbilityScoreBonus },
+    ArmorClass { bonus: ArmorClassBonus },
+    Damage { kind: DamageAdjustment, types: Vec<DamageType> },
+    HitPointMaximum { bonus: HitPointMaximumBonus },
+    Speed { bonus: SpeedBonus },
+    /// The character dies, as at the sixth level of exhaustion.
+    Death,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(tag = "type")]
 pub enum AbilityScoreBonus {
     Modifier { modifier: isize },
+    /// The score is at least `value`, e.g. Gauntlets of Ogre Power.
     Become { value: isize },
 }
 
+impl Display for AbilityScoreBonus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbilityScoreBonus::Modifier { modifier } if *modifier < 0 => write!(f, "{}", modifier),
+            AbilityScoreBonus::Modifier { modifier } => write!(f, "+{}", modifier),
+            AbilityScoreBonus::Become { value } => write!(f, "at least {}", value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(tag = "type", content = "value")]
 pub enum RollBonus {
-    // Reroll(HashSet<isize>),
-    // Advantage(Advantage),
+    Reroll(Reroll),
+    /// The lowest natural d20 result that is a critical hit, e.g. 19 for an Improved Critical.
+    CriticalRange(isize),
+    Advantage(Advantage),
     Modifier(isize),
     Roll(Roll),
     Proficiency,
+    /// Twice the proficiency bonus.
+    Expertise,
+    /// Half the proficiency bonus, e.g. Jack of All Trades or Remarkable Athlete.
+    HalfProficiency(Rounding),
     Ability(Ability),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+impl RollBonus {
+    /// The bonus granted by a kind of proficiency. Only the best one applies to a roll.
+    pub fn proficiency(&self, proficiency_bonus: isize) -> Option<isize> {
+        match self {
+            RollBonus::Proficiency => Some(proficiency_bonus),
+            RollBonus::Expertise => Some(proficiency_bonus * 2),
+            RollBonus::HalfProficiency(Rounding::Down) => Some(proficiency_bonus / 2),
+            RollBonus::HalfProficiency(Rounding::Up) => Some((proficiency_bonus + 1) / 2),
+            _ => None,
+        }
+    }
+}
+
+/// Dice showing one of `faces` are rolled again, once, e.g. Halfling Luck or Great Weapon Fighting.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Reroll {
+    pub faces: HashSet<isize>,
+    /// Only reroll dice with this many sides, such as the d20 for Halfling Luck.
+    #[serde(default)]
+    pub sides: Option<isize>,
+    #[serde(default)]
+    pub keep: RerollKeep,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub enum RerollKeep {
+    /// The new result must be used, even if it is lower.
+    #[default]
+    New,
+    /// The higher of the two results is used.
+    Higher,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Advantage {
+    Advantage,
+    Disadvantage,
+}
+
+impl Advantage {
+    /// Any number of sources of advantage and disadvantage cancel each other out.
+    pub fn combine(advantages: &[Advantage]) -> Option<Advantage> {
+        let advantage = advantages.contains(&Advantage::Advantage);
+        let disadvantage = advantages.contains(&Advantage::Disadvantage);
+        match (advantage, disadvantage) {
+            (true, false) => Some(Advantage::Advantage),
+            (false, true) => Some(Advantage::Disadvantage),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Advantage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
 pub struct RollScope {
     pub path: Option<Vec<String>>,
@@ -35,18 +128,30 @@ pub struct RollScope {
 }
 
 impl RollScope {
-    pub fn matches(&self, path: &Vec<String>) -> bool {
-        match &self.path {
-            Some(scope) => {
-                &path
-                    .clone()
-                    .into_iter()
-                    .take(scope.len())
-                    .collect::<Vec<String>>()
-                    == scope
-            }
-            None => false,
+    /// Every criterion the scope specifies must match the roll; a scope without any criteria
+    /// matches nothing.
+    pub fn matches(&self, path: &[String], context: &RollContext) -> bool {
+        if self.path.is_none() && self.ability.is_none() && self.range.is_none() {
+            return false;
         }
+
+        let path_matches = match &self.path {
+            Some(scope) => path.starts_with(scope),
+            None => true,
+        };
+        let ability_matches = match &self.ability {
+            Some(ability) => context.ability.as_ref() == Some(ability),
+            None => true,
+        };
+        let range_matches = match &self.range {
+            Some(range) => context
+                .range
+                .as_ref()
+                .is_some_and(|context| range.includes(context)),
+            None => true,
+        };
+
+        path_matches && ability_matches && range_matches
     }
 }
 
@@ -54,7 +159,27 @@ impl RollScope {
 #[serde(tag = "type", content = "value")]
 pub enum Range {
     Melee,
-    Ranged { normal: isize, long: isize },
+    Ranged {
+        #[serde(default)]
+        normal: isize,
+        #[serde(default)]
+        long: isize,
+    },
+}
+
+impl Range {
+    /// The range of weapons and spell attacks that don't give one.
+    pub fn melee() -> Range {
+        Range::Melee
+    }
+
+    /// Ranges match by kind, so a scope of `Ranged` applies at any distance.
+    pub fn includes(&self, other: &Range) -> bool {
+        matches!(
+            (self, other),
+            (Range::Melee, Range::Melee) | (Range::Ranged { .. }, Range::Ranged { .. })
+        )
+    }
 }
 
 impl Display for Range {
@@ -65,3 +190,58 @@ impl Display for Range {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Range, RollBonus, RollScope, Rounding};
+    use crate::domain::ability_score::Ability;
+    use crate::domain::roll::RollContext;
+
+    fn path(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let ranged = RollContext {
+            ability: Some(Ability::Dexterity),
+            range: Some(Range::Ranged {
+                normal: 80,
+                long: 320,
+            }),
+            ..RollContext::default()
+        };
+        let archery = RollScope {
+            path: Some(path(&["attack"])),
+            range: Some(Range::Ranged { normal: 0, long: 0 }),
+            ..RollScope::default()
+        };
+        assert!(archery.matches(&path(&["attack", "longbow"]), &ranged));
+        assert!(!archery.matches(&path(&["skill", "stealth"]), &ranged));
+        assert!(!archery.matches(
+            &path(&["attack", "longbow"]),
+            &RollContext {
+                range: Some(Range::Melee),
+                ..ranged.clone()
+            }
+        ));
+
+        let dexterity = RollScope {
+            ability: Some(Ability::Dexterity),
+            ..RollScope::default()
+        };
+        assert!(dexterity.matches(&path(&["skill", "stealth"]), &ranged));
+        assert!(!dexterity.matches(&path(&["skill", "stealth"]), &RollContext::default()));
+
+        assert!(!RollScope::default().matches(&path(&["skill"]), &ranged));
+    }
+
+    #[test]
+    fn test_proficiency() {
+        assert_eq!(RollBonus::Proficiency.proficiency(3), Some(3));
+        assert_eq!(RollBonus::Expertise.proficiency(3), Some(6));
+        assert_eq!(RollBonus::HalfProficiency(Rounding::Down).proficiency(3), Some(1));
+        assert_eq!(RollBonus::HalfProficiency(Rounding::Up).proficiency(3), Some(2));
+        assert_eq!(RollBonus::Modifier(3).proficiency(3), None);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::effect::{Advantage, Effect, RollBonus, RollScope};
+use crate::domain::inventory::Weight;
+use crate::domain::speed::SpeedBonus;
+use crate::render::Render;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub enum Size {
+    Tiny,
+    Small,
+    #[default]
+    Medium,
+    Large,
+    Huge,
+    Gargantuan,
+}
+
+impl Size {
+    /// Carrying capacity, as a percentage, is halved for each size smaller than Small and doubled
+    /// for each size larger than Medium.
+    fn capacity_percent(&self) -> isize {
+        match self {
+            Size::Tiny => 50,
+            Size::Small | Size::Medium => 100,
+            Size::Large => 200,
+            Size::Huge => 400,
+            Size::Gargantuan => 800,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Encumbrance {
+    Unencumbered,
+    Encumbered,
+    HeavilyEncumbered,
+    OverCapacity,
+}
+
+impl Display for Encumbrance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encumbrance::Unencumbered => write!(f, "Unencumbered"),
+            Encumbrance::Encumbered => write!(f, "Encumbered"),
+            Encumbrance::HeavilyEncumbered => write!(f, "Heavily Encumbered"),
+            Encumbrance::OverCapacity => write!(f, "Over carrying capacity"),
+        }
+    }
+}
+
+/// How much a character carries compared to what their Strength and size allow. The encumbered
+/// and heavily encumbered thresholds only apply with the variant encumbrance rules.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EncumbranceResult {
+    carried: Weight,
+    strength: isize,
+    size: Size,
+    variant: bool,
+}
+
+impl EncumbranceResult {
+    pub fn new(carried: Weight, strength: isize, size: Size, variant: bool) -> EncumbranceResult {
+        EncumbranceResult {
+            carried,
+            strength,
+            size,
+            variant,
+        }
+    }
+
+    fn threshold(&self, per_strength: isize) -> Weight {
+        // A percentage of pounds is hundredths of a pound.
+        Weight::from_hundredths(self.strength * per_strength * self.size.capacity_percent())
+    }
+
+    pub fn capacity(&self) -> Weight {
+        self.threshold(15)
+    }
+
+    pub fn encumbrance(&self) -> Encumbrance {
+        if self.carried > self.capacity() {
+            Encumbrance::OverCapacity
+        } else if self.variant && self.carried > self.threshold(10) {
+            Encumbrance::HeavilyEncumbered
+        } else if self.variant && self.carried > self.threshold(5) {
+            Encumbrance::Encumbered
+        } else {
+            Encumbrance::Unencumbered
+        }
+    }
+
+    /// With the variant rules, being encumbered reduces speed by 10 feet. Being heavily encumbered
+    /// reduces it by 20 feet instead, and gives disadvantage on ability checks, attack rolls and
+    /// saving throws that use Strength, Dexterity or Constitution.
+    pub fn effects(&self) -> Vec<(Vec<String>, Effect)> {
+        if !self.variant {
+            return vec![];
+        }
+        let encumbrance = self.encumbrance();
+        let path = vec![encumbrance.to_string()];
+        match encumbrance {
+            Encumbrance::Unencumbered => vec![],
+            Encumbrance::Encumbered => vec![(
+                path,
+                Effect::Speed {
+                    bonus: SpeedBonus::Modifier { modifier: -10 },
+                },
+            )],
+            Encumbrance::HeavilyEncumbered | Encumbrance::OverCapacity => {
+                let mut effects = vec![(
+                    path.clone(),
+                    Effect::Speed {
+                        bonus: SpeedBonus::Modifier { modifier: -20 },
+                    },
+                )];
+                for roll in ["ability", "skill", "initiative", "attack", "saving-throw"] {
+                    for ability in [Ability::Strength, Ability::Dexterity, Ability::Constitution] {
+                        effects.push((
+                            path.clone(),
+                            Effect::Roll {
+                                bonus: RollBonus::Advantage(Advantage::Disadvantage),
+                                scope: RollScope {
+                                    path: Some(vec![roll.to_string()]),
+                                    ability: Some(ability),
+                                    range: None,
+                                },
+                            },
+                        ));
+                    }
+                }
+                effects
+            }
+        }
+    }
+}
+
+impl Render for EncumbranceResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let tab = <dyn Render>::tab(indent);
+        out.write_fmt(format_args!(
+            "{}Carrying {} of {} (Strength {}, {:?})\n",
+            tab,
+            self.carried,
+            self.capacity(),
+            self.strength,
+            self.size
+        ))?;
+        if self.variant {
+            out.write_fmt(format_args!(
+                "{}Encumbered over {}, heavily encumbered over {}\n",
+                tab,
+                self.threshold(5),
+                self.threshold(10)
+            ))?;
+        }
+        out.write_fmt(format_args!("{}{}\n", tab, self.encumbrance()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Encumbrance, EncumbranceResult, Size};
+    use crate::domain::effect::Effect;
+    use crate::domain::inventory::Weight;
+    use crate::domain::speed::SpeedBonus;
+
+    #[test]
+    fn test_encumbrance() {
+        let result = |carried: f64, size: Size, variant: bool| EncumbranceResult::new(Weight::from_pounds(carried).unwrap(), 10, size, variant);
+
+        assert_eq!(result(150.0, Size::Medium, false).encumbrance(), Encumbrance::Unencumbered);
+        assert_eq!(result(151.0, Size::Medium, false).encumbrance(), Encumbrance::OverCapacity);
+        assert_eq!(result(151.0, Size::Large, false).encumbrance(), Encumbrance::Unencumbered);
+        assert_eq!(result(76.0, Size::Tiny, false).encumbrance(), Encumbrance::OverCapacity);
+        assert_eq!(result(50.0, Size::Medium, true).encumbrance(), Encumbrance::Unencumbered);
+        assert_eq!(result(51.0, Size::Medium, true).encumbrance(), Encumbrance::Encumbered);
+        assert_eq!(result(101.0, Size::Medium, true).encumbrance(), Encumbrance::HeavilyEncumbered);
+
+        assert!(result(101.0, Size::Medium, false).effects().is_empty());
+        assert_eq!(
+            result(51.0, Size::Medium, true).effects(),
+            vec![(
+                vec!["Encumbered".to_string()],
+                Effect::Speed {
+                    bonus: SpeedBonus::Modifier { modifier: -10 },
+                },
+            )]
+        );
+        assert_eq!(result(101.0, Size::Medium, true).effects().len(), 16);
+    }
+}
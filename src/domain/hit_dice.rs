@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+
+/// Hit dice spent since the last long rest, by number of sides. The pool itself comes from the
+/// character's classes.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct HitDice {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    spent: BTreeMap<isize, isize>,
+}
+
+impl HitDice {
+    pub fn remaining(&self, total: &BTreeMap<isize, isize>) -> BTreeMap<isize, isize> {
+        total
+            .iter()
+            .map(|(sides, count)| {
+                let spent = self.spent.get(sides).copied().unwrap_or(0);
+                (*sides, max(0, count - spent))
+            })
+            .collect()
+    }
+
+    /// Spends a hit die with the given number of sides, or the largest remaining one. Returns the
+    /// sides of the spent die, if there was one left.
+    pub fn spend(&mut self, total: &BTreeMap<isize, isize>, sides: Option<isize>) -> Option<isize> {
+        let remaining = self.remaining(total);
+        let sides = match sides {
+            Some(sides) => Some(sides).filter(|sides| remaining.get(sides).copied().unwrap_or(0) > 0),
+            None => remaining
+                .iter()
+                .rev()
+                .find(|(_, count)| **count > 0)
+                .map(|(sides, _)| *sides),
+        }?;
+        *self.spent.entry(sides).or_insert(0) += 1;
+        Some(sides)
+    }
+
+    /// Regains up to `count` spent hit dice, the largest first. Returns the regained dice by
+    /// number of sides.
+    pub fn regain(&mut self, count: isize) -> BTreeMap<isize, isize> {
+        let mut left = count;
+        let mut regained = BTreeMap::new();
+        for (sides, spent) in self.spent.iter_mut().rev() {
+            let amount = min(left, *spent);
+            if amount > 0 {
+                *spent -= amount;
+                left -= amount;
+                regained.insert(*sides, amount);
+            }
+        }
+        self.spent.retain(|_, spent| *spent > 0);
+        regained
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HitDice;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_spend() {
+        let total = vec![(6, 1), (10, 2)].into_iter().collect::<BTreeMap<isize, isize>>();
+        let mut hit_dice = HitDice::default();
+        assert_eq!(hit_dice.spend(&total, None), Some(10));
+        assert_eq!(hit_dice.spend(&total, Some(6)), Some(6));
+        assert_eq!(hit_dice.spend(&total, Some(6)), None);
+        assert_eq!(hit_dice.spend(&total, Some(8)), None);
+        assert_eq!(hit_dice.spend(&total, None), Some(10));
+        assert_eq!(hit_dice.spend(&total, None), None);
+        assert_eq!(
+            hit_dice.remaining(&total),
+            vec![(6, 0), (10, 0)].into_iter().collect()
+        );
+
+        assert_eq!(hit_dice.regain(2), vec![(10, 2)].into_iter().collect());
+        assert_eq!(hit_dice.regain(2), vec![(6, 1)].into_iter().collect());
+        assert_eq!(hit_dice, HitDice::default());
+    }
+}
@@ -2,60 +2,367 @@ use std::cmp::{max, min};
 use crate::render::Render;
 use std::io::Write;
 use serde::{Serialize, Deserialize};
+use crate::domain::damage::{AdjustedDamage, Damage, DamageResult};
+use crate::domain::effect::Effect;
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
 pub struct HitPoints {
     current: isize,
     max: isize,
-    temporary: isize
+    /// Temporary hit points, a separate pool that absorbs damage before `current`.
+    temporary: isize,
+    /// Raises or lowers the maximum, e.g. from Aid.
+    #[serde(default)]
+    max_modifier: isize,
+    #[serde(default)]
+    death_saves: DeathSaves,
+    /// Set from effects such as exhaustion rather than stored.
+    #[serde(skip)]
+    max_halved: bool,
+    /// Dead from an effect such as exhaustion rather than from death saves.
+    #[serde(skip)]
+    killed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum HitPointMaximumBonus {
+    /// Halves the maximum, rounding down, as with the fourth level of exhaustion.
+    Halved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct DeathSaves {
+    successes: isize,
+    failures: isize,
 }
 
 impl HitPoints {
+    pub fn current(&self) -> isize {
+        self.current
+    }
+
+    pub fn temporary(&self) -> isize {
+        self.temporary
+    }
+
+    pub fn has_death_saves(&self) -> bool {
+        self.death_saves != DeathSaves::default()
+    }
+
     pub fn increase_max(&mut self, increment: isize) {
         self.max += increment;
     }
 
     pub fn get_modified_max(&self) -> isize {
-        self.max + self.temporary
+        if self.max_halved {
+            (self.max + self.max_modifier) / 2
+        } else {
+            self.max + self.max_modifier
+        }
+    }
+
+    /// Applies the effects that change hit points. Current hit points above a reduced maximum are
+    /// lost, and aren't regained when the maximum is restored.
+    pub fn apply_effects(&mut self, effects: &[(Vec<String>, Effect)]) {
+        self.max_halved = effects.iter().any(|(_, effect)| {
+            matches!(
+                effect,
+                Effect::HitPointMaximum {
+                    bonus: HitPointMaximumBonus::Halved
+                }
+            )
+        });
+        self.killed = effects.iter().any(|(_, effect)| effect == &Effect::Death);
+        self.current = max(0, min(self.current, self.get_modified_max()));
+    }
+
+    /// Changing the maximum changes current hit points by the same amount, as with Aid.
+    pub fn add_max_modifier(&mut self, delta: isize) {
+        self.max_modifier += delta;
+        if delta > 0 {
+            self.heal(delta);
+        } else {
+            self.current = max(0, min(self.current, self.get_modified_max()));
+        }
+    }
+
+    pub fn reset_max_modifier(&mut self) {
+        self.add_max_modifier(-self.max_modifier);
     }
 
     pub fn add_current(&mut self, delta: isize) -> bool {
-        let result = self.current + delta;
-        let result = max(0, result);
-        let result = min(result, self.get_modified_max());
-        self.current = result;
+        if delta < 0 {
+            self.damage(-delta);
+        } else {
+            self.heal(delta);
+        }
         self.is_conscious()
     }
 
-    pub fn add_temporary(&mut self, delta: isize) {
-        self.temporary += delta;
-        self.add_current(delta);
+    /// Healing brings an unconscious character back and clears their death saves, but cannot
+    /// revive the dead.
+    pub fn heal(&mut self, amount: isize) {
+        if self.is_dead() {
+            return;
+        }
+        let result = min(self.current + max(0, amount), self.get_modified_max());
+        self.current = max(self.current, result);
+        if self.current > 0 {
+            self.death_saves = DeathSaves::default();
+        }
+    }
+
+    /// Applies resistance, vulnerability and immunity from `effects` to each instance of damage
+    /// before taking the total.
+    pub fn take_damage(&mut self, damage: Vec<Damage>, effects: &[(Vec<String>, Effect)]) -> DamageResult {
+        let instances = damage
+            .into_iter()
+            .map(|damage| {
+                let adjustments = effects
+                    .iter()
+                    .flat_map(|(path, effect)| match (effect, &damage.damage_type) {
+                        (Effect::Damage { kind, types }, Some(damage_type))
+                            if types.contains(damage_type) =>
+                        {
+                            Some((path.clone(), kind.clone()))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                AdjustedDamage::new(damage, adjustments)
+            })
+            .collect();
+
+        let result = DamageResult::new(instances);
+        self.damage(result.total());
+        result
+    }
+
+    /// Damage is taken from temporary hit points first. Damage at 0 hit points is a failed death
+    /// save, and damage that leaves at least the maximum hit points remaining after reaching 0 is
+    /// instant death.
+    pub fn damage(&mut self, amount: isize) {
+        let absorbed = min(self.temporary, max(0, amount));
+        self.temporary -= absorbed;
+        let amount = max(0, amount) - absorbed;
+        if amount == 0 || self.is_dead() {
+            return;
+        }
+        let remaining = amount - self.current;
+        let was_conscious = self.is_conscious();
+        self.current = max(0, self.current - amount);
+
+        if remaining >= self.get_modified_max() {
+            self.death_saves.failures = 3;
+        } else if !was_conscious {
+            if self.is_stable() {
+                self.death_saves = DeathSaves::default();
+            }
+            self.fail_death_saves(1);
+        }
+    }
+
+    /// Records the result of a death saving throw: a natural 1 counts as two failures and a
+    /// natural 20 regains 1 hit point.
+    pub fn death_save(&mut self, natural: isize, total: isize) {
+        if self.is_conscious() || self.is_dead() || self.is_stable() {
+            return;
+        }
+        if natural >= 20 {
+            self.heal(1);
+        } else if natural <= 1 {
+            self.fail_death_saves(2);
+        } else if total >= 10 {
+            self.death_saves.successes = min(3, self.death_saves.successes + 1);
+        } else {
+            self.fail_death_saves(1);
+        }
+    }
+
+    fn fail_death_saves(&mut self, failures: isize) {
+        self.death_saves.failures = min(3, self.death_saves.failures + failures);
+    }
+
+    /// Temporary hit points don't stack, the higher of the two pools is kept.
+    pub fn add_temporary(&mut self, amount: isize) {
+        self.temporary = max(self.temporary, amount);
     }
 
     pub fn reset_temporary(&mut self) {
         self.temporary = 0;
-        let current = min(self.current, self.get_modified_max());
-        self.current = current;
     }
 
     pub fn reset(&mut self) {
         self.reset_temporary();
         self.current = self.get_modified_max();
+        self.death_saves = DeathSaves::default();
     }
 
     pub fn is_conscious(&self) -> bool {
         self.current > 0
     }
+
+    pub fn is_dead(&self) -> bool {
+        self.killed || self.death_saves.failures >= 3
+    }
+
+    pub fn is_stable(&self) -> bool {
+        !self.is_conscious() && self.death_saves.successes >= 3
+    }
 }
 use anyhow::Result;
 impl Render for HitPoints {
     fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
         out.write_fmt(format_args!("{}Hit Points:\n", Render::tab(indent)))?;
-        if self.temporary == 0 {
+        if self.max_modifier == 0 {
             out.write_fmt(format_args!("{} {} / {}\n", Render::tab(indent + 1), self.current, self.get_modified_max()))?;
         } else {
-            let diff_char = if self.temporary < 0 { '-' } else { '+' };
-            out.write_fmt(format_args!("{} {} / {} [ {} {} {} ]\n", Render::tab(indent + 1), self.current, self.get_modified_max(), self.max, diff_char, self.temporary.abs()))?;
+            let diff_char = if self.max_modifier < 0 { '-' } else { '+' };
+            out.write_fmt(format_args!("{} {} / {} [ {} {} {} ]\n", Render::tab(indent + 1), self.current, self.get_modified_max(), self.max, diff_char, self.max_modifier.abs()))?;
+        }
+        if self.max_halved {
+            out.write_fmt(format_args!("{} Maximum halved\n", <dyn Render>::tab(indent + 1)))?;
+        }
+        if self.temporary > 0 {
+            out.write_fmt(format_args!("{} Temporary: {}\n", <dyn Render>::tab(indent + 1), self.temporary))?;
+        }
+        if self.is_dead() {
+            out.write_fmt(format_args!("{} Dead\n", <dyn Render>::tab(indent + 1)))?;
+        } else if self.is_stable() {
+            out.write_fmt(format_args!("{} Unconscious — stable\n", <dyn Render>::tab(indent + 1)))?;
+        } else if !self.is_conscious() {
+            out.write_fmt(format_args!(
+                "{} Unconscious — {} / {}\n",
+                <dyn Render>::tab(indent + 1),
+                plural(self.death_saves.successes, "success", "successes"),
+                plural(self.death_saves.failures, "failure", "failures")
+            ))?;
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn plural(count: isize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, singular)
+    } else {
+        format!("{} {}", count, plural)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeathSaves, HitPointMaximumBonus, HitPoints};
+    use crate::domain::effect::Effect;
+
+    fn with_current(current: isize, max: isize) -> HitPoints {
+        HitPoints {
+            current,
+            max,
+            ..HitPoints::default()
+        }
+    }
+
+    #[test]
+    fn test_damage_and_healing() {
+        let mut hit_points = with_current(10, 20);
+        hit_points.damage(15);
+        assert!(!hit_points.is_conscious());
+        assert!(!hit_points.is_dead());
+
+        hit_points.damage(3);
+        assert_eq!(hit_points.death_saves.failures, 1);
+
+        hit_points.heal(4);
+        assert_eq!(hit_points.current, 4);
+        assert_eq!(hit_points.death_saves, DeathSaves::default());
+    }
+
+    #[test]
+    fn test_temporary() {
+        let mut hit_points = with_current(10, 20);
+        hit_points.add_temporary(5);
+        hit_points.add_temporary(3);
+        assert_eq!(hit_points.temporary, 5, "Temporary hit points don't stack");
+
+        hit_points.damage(7);
+        assert_eq!((hit_points.temporary, hit_points.current), (0, 8));
+
+        hit_points.add_max_modifier(5);
+        assert_eq!((hit_points.current, hit_points.get_modified_max()), (13, 25));
+        hit_points.reset_max_modifier();
+        assert_eq!((hit_points.current, hit_points.get_modified_max()), (13, 20));
+
+        let mut hit_points = with_current(5, 20);
+        hit_points.add_temporary(10);
+        hit_points.damage(34);
+        assert!(!hit_points.is_dead(), "Temporary hit points don't count towards massive damage");
+        hit_points.heal(5);
+        hit_points.damage(25);
+        assert!(hit_points.is_dead());
+    }
+
+    #[test]
+    fn test_massive_damage() {
+        let mut hit_points = with_current(10, 20);
+        hit_points.damage(30);
+        assert!(hit_points.is_dead());
+
+        hit_points.heal(5);
+        assert_eq!(hit_points.current, 0, "The dead cannot be healed");
+
+        let mut hit_points = with_current(0, 20);
+        hit_points.damage(20);
+        assert!(hit_points.is_dead());
+    }
+
+    #[test]
+    fn test_death_saves() {
+        let mut hit_points = with_current(0, 20);
+        hit_points.death_save(12, 12);
+        hit_points.death_save(5, 9);
+        assert_eq!(
+            hit_points.death_saves,
+            DeathSaves {
+                successes: 1,
+                failures: 1
+            }
+        );
+
+        hit_points.death_save(1, 1);
+        assert!(hit_points.is_dead());
+
+        let mut hit_points = with_current(0, 20);
+        hit_points.death_save(20, 22);
+        assert_eq!(hit_points.current, 1);
+
+        let mut hit_points = with_current(0, 20);
+        (0..3).for_each(|_| hit_points.death_save(15, 15));
+        assert!(hit_points.is_stable());
+
+        hit_points.damage(1);
+        assert!(!hit_points.is_stable());
+        assert_eq!(hit_points.death_saves.failures, 1);
+    }
+
+    #[test]
+    fn test_effects() {
+        let exhaustion = vec!["exhaustion".to_string()];
+        let halved = (
+            exhaustion.clone(),
+            Effect::HitPointMaximum {
+                bonus: HitPointMaximumBonus::Halved,
+            },
+        );
+        let mut hit_points = with_current(15, 21);
+        hit_points.apply_effects(std::slice::from_ref(&halved));
+        assert_eq!((hit_points.current, hit_points.get_modified_max()), (10, 10));
+        hit_points.apply_effects(&[]);
+        assert_eq!((hit_points.current, hit_points.get_modified_max()), (10, 21));
+
+        hit_points.apply_effects(&[halved, (exhaustion, Effect::Death)]);
+        assert!(hit_points.is_dead());
+        hit_points.heal(5);
+        assert_eq!(hit_points.current, 10);
+    }
+}
@@ -1,9 +1,17 @@
+use crate::domain::armor_class::Armor;
+use crate::domain::currency::Coins;
+use crate::domain::effect::Effect;
+use crate::domain::weapon::Weapon;
 use crate::render::Render;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
 use std::io::Write;
+use std::iter::Sum;
+use std::ops::Add;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
 pub struct Inventory {
@@ -34,15 +42,14 @@ pub enum RemoveItemResult {
 impl Render for (&String, &InventoryItem) {
     fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
         match self {
-            (name, InventoryItem::Object { count }) => {
+            (name, InventoryItem::Object { .. }) => {
                 out.write_fmt(format_args!(
-                    "{}x{} ... {}\n",
-                    Render::tab(indent),
-                    count,
-                    name
+                    "{}{}\n",
+                    <dyn Render>::tab(indent),
+                    self.1.label(name)
                 ))?;
             }
-            (name, InventoryItem::Container { items }) => {
+            (name, InventoryItem::Container { items, .. }) => {
                 out.write_fmt(format_args!("{}{}:\n", Render::tab(indent), name))?;
                 for name_item in items.iter().sorted_by_key(|(n, i)| n.clone()) {
                     name_item.render(indent + 1, out)?;
@@ -63,6 +70,206 @@ impl Render for Inventory {
     }
 }
 
+/// A weight, kept in hundredths of a pound so that sums are exact. It is written in pounds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd, Default)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Weight(isize);
+
+impl Weight {
+    pub fn from_hundredths(hundredths: isize) -> Weight {
+        Weight(hundredths)
+    }
+
+    /// Rounds to the nearest hundredth of a pound. Weights can't be negative, infinite or NaN.
+    pub fn from_pounds(pounds: f64) -> Result<Weight> {
+        if !pounds.is_finite() || pounds < 0.0 {
+            return Err(anyhow!("{} is not a weight in pounds", pounds));
+        }
+        Ok(Weight((pounds * 100.0).round() as isize))
+    }
+
+    pub fn times(&self, count: isize) -> Weight {
+        Weight(self.0 * count)
+    }
+}
+
+impl TryFrom<f64> for Weight {
+    type Error = anyhow::Error;
+
+    fn try_from(pounds: f64) -> Result<Weight> {
+        Weight::from_pounds(pounds)
+    }
+}
+
+impl From<Weight> for f64 {
+    fn from(weight: Weight) -> f64 {
+        weight.0 as f64 / 100.0
+    }
+}
+
+impl Add for Weight {
+    type Output = Weight;
+
+    fn add(self, other: Weight) -> Weight {
+        Weight(self.0 + other.0)
+    }
+}
+
+impl Sum for Weight {
+    fn sum<I: Iterator<Item = Weight>>(iter: I) -> Weight {
+        iter.fold(Weight::default(), Add::add)
+    }
+}
+
+impl Display for Weight {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pounds = format!("{}.{:02}", self.0 / 100, self.0 % 100);
+        write!(f, "{} lb", pounds.trim_end_matches('0').trim_end_matches('.'))
+    }
+}
+
+/// Descriptive details of an object. A catalog of them for every item of a kind, such as rope or a
+/// longsword, lets objects refer to an entry instead of repeating them.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct ItemDetails {
+    /// The weight of one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<Weight>,
+    /// The cost of one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<Coins>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl ItemDetails {
+    fn is_empty(&self) -> bool {
+        self == &ItemDetails::default()
+    }
+
+    /// These details, with any that are missing taken from `other`.
+    fn or(&self, other: &ItemDetails) -> ItemDetails {
+        ItemDetails {
+            weight: self.weight.or(other.weight),
+            cost: self.cost.clone().or_else(|| other.cost.clone()),
+            description: self.description.clone().or_else(|| other.description.clone()),
+            tags: if self.tags.is_empty() {
+                other.tags.clone()
+            } else {
+                self.tags.clone()
+            },
+        }
+    }
+}
+
+/// Details of a container itself rather than its contents.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct ContainerDetails {
+    /// The weight of the empty container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<Weight>,
+    /// The contents don't count towards the weight carried, e.g. a Bag of Holding.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub weightless_contents: bool,
+}
+
+impl ContainerDetails {
+    pub fn is_empty(&self) -> bool {
+        self == &ContainerDetails::default()
+    }
+}
+
+/// The inventory with columns for the weight and value of each stack, and subtotals for each
+/// container.
+pub struct InventoryListing {
+    pub inventory: Inventory,
+    pub weight: bool,
+    pub value: bool,
+}
+
+struct ListingRow {
+    indent: usize,
+    label: String,
+    /// Container names head their contents and have no columns of their own.
+    heading: bool,
+    weight: Option<Weight>,
+    value: Option<isize>,
+}
+
+impl InventoryListing {
+    fn rows(name: &str, item: &InventoryItem, indent: usize, rows: &mut Vec<ListingRow>) {
+        match item {
+            InventoryItem::Object { count, details, .. } => rows.push(ListingRow {
+                indent,
+                label: item.label(name),
+                heading: false,
+                weight: details.weight.map(|weight| weight.times(*count)),
+                value: details.cost.as_ref().map(|cost| cost.in_copper() * count),
+            }),
+            InventoryItem::Container { items, .. } => {
+                rows.push(ListingRow {
+                    indent,
+                    label: format!("{}:", name),
+                    heading: true,
+                    weight: None,
+                    value: None,
+                });
+                for (name, item) in items.iter().sorted_by_key(|(name, _)| name.to_string()) {
+                    InventoryListing::rows(name, item, indent + 1, rows);
+                }
+                rows.push(ListingRow {
+                    indent: indent + 1,
+                    label: "subtotal".to_string(),
+                    heading: false,
+                    weight: Some(item.total_weight()),
+                    value: Some(item.total_value()),
+                });
+            }
+        }
+    }
+}
+
+impl Render for InventoryListing {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let mut rows = vec![];
+        for (name, item) in self.inventory.items.iter().sorted_by_key(|(name, _)| name.to_string()) {
+            InventoryListing::rows(name, item, indent + 1, &mut rows);
+        }
+        rows.push(ListingRow {
+            indent: indent + 1,
+            label: "Total".to_string(),
+            heading: false,
+            weight: Some(self.inventory.total_weight()),
+            value: Some(self.inventory.items.values().map(|item| item.total_value()).sum()),
+        });
+
+        // Tabs are only used for indentation, so each one is a full tab stop wide.
+        let width = |row: &ListingRow| row.indent * 8 + row.label.chars().count();
+        let widest = rows.iter().map(width).max().unwrap_or(0);
+        out.write_fmt(format_args!("{}Inventory\n", <dyn Render>::tab(indent)))?;
+        for row in &rows {
+            let mut line = format!(
+                "{}{}{}",
+                <dyn Render>::tab(row.indent),
+                row.label,
+                " ".repeat(widest - width(row))
+            );
+            if self.weight && !row.heading {
+                let weight = row.weight.map(|weight| weight.to_string());
+                line.push_str(&format!("  {:>10}", weight.unwrap_or_else(|| "-".to_string())));
+            }
+            if self.value && !row.heading {
+                let value = row.value.map(|value| Coins::from_copper(value).to_string());
+                line.push_str(&format!("  {:>16}", value.unwrap_or_else(|| "-".to_string())));
+            }
+            out.write_fmt(format_args!("{}\n", line.trim_end()))?;
+        }
+        Ok(())
+    }
+}
+
 fn path_string(path: &Vec<String>) -> String {
     path.iter().join(" / ")
 }
@@ -260,6 +467,199 @@ impl Render for AddContainerResult {
 
     }
 }
+/// What happens to the contents of a container that is removed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RemoveContainerMode {
+    /// Only remove the container if it is empty.
+    IfEmpty,
+    /// Move the contents into the parent container.
+    Spill,
+    /// Remove the contents along with the container.
+    Discard,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RemoveContainerResult {
+    Success {
+        path: Vec<String>,
+        spilled: Vec<String>,
+        discarded: Vec<String>,
+    },
+    NotEmpty { path: Vec<String>, count: usize },
+    ExpectedContainer { path: Vec<String> },
+    NoSuchContainer { path: Vec<String> },
+    /// Spilling would replace something already in the parent container.
+    Collision { path: Vec<String>, names: Vec<String> },
+    PathIsEmpty { path: Vec<String> },
+}
+
+impl Render for RemoveContainerResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        match self {
+            RemoveContainerResult::Success {
+                path,
+                spilled,
+                discarded,
+            } => {
+                out.write_fmt(format_args!("{}{}: Successfully removed container", <dyn Render>::tab(indent), path_string(path)))?;
+                if !spilled.is_empty() {
+                    out.write_fmt(format_args!(", spilled {}", spilled.join(", ")))?;
+                }
+                if !discarded.is_empty() {
+                    out.write_fmt(format_args!(", discarded {}", discarded.join(", ")))?;
+                }
+                out.write_fmt(format_args!("\n"))?;
+            }
+            RemoveContainerResult::NotEmpty { path, count } => {
+                out.write_fmt(format_args!("{}{}: The container holds {} items, use --spill or --discard\n", <dyn Render>::tab(indent), path_string(path), count))?;
+            }
+            RemoveContainerResult::ExpectedContainer { path } => {
+                out.write_fmt(format_args!("{}{}: The specified path is not a container\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            RemoveContainerResult::NoSuchContainer { path } => {
+                out.write_fmt(format_args!("{}{}: there was nothing there\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            RemoveContainerResult::Collision { path, names } => {
+                out.write_fmt(format_args!("{}{}: Cannot spill {} into a container that already has them\n", <dyn Render>::tab(indent), path_string(path), names.join(", ")))?;
+            }
+            RemoveContainerResult::PathIsEmpty { path } => {
+                out.write_fmt(format_args!("{}{}: No path was provided\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RemoveContainerResult {
+    /// The path of the container, if it was removed.
+    pub fn removed(&self) -> Option<Vec<String>> {
+        match self {
+            RemoveContainerResult::Success { path, .. } => Some(path.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MoveItemResult {
+    /// `count` is the size of the moved stack, or `None` when a container was moved. `emptied` is
+    /// whether nothing was left behind at `from`.
+    Success {
+        from: Vec<String>,
+        to: Vec<String>,
+        count: Option<isize>,
+        emptied: bool,
+    },
+    InvalidPath { path: Vec<String> },
+    NoSuchItem { path: Vec<String> },
+    InsufficientInventory {
+        path: Vec<String>,
+        requested: isize,
+        available: isize,
+    },
+    CannotSplitContainer { path: Vec<String> },
+    ContainerDoesNotExistFor { path: Vec<String> },
+    DestinationIsObject { path: Vec<String> },
+    /// A container with the moved item's name is already at the destination.
+    DestinationOccupied { path: Vec<String> },
+    IntoItself { path: Vec<String> },
+}
+
+impl Render for MoveItemResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        match self {
+            MoveItemResult::Success { from, to, count, .. } => {
+                let moved = match count {
+                    Some(count) => format!("moved {}", count),
+                    None => "moved container".to_string(),
+                };
+                out.write_fmt(format_args!("{}{} → {}: {}\n", <dyn Render>::tab(indent), path_string(from), path_string(to), moved))?;
+            }
+            MoveItemResult::InvalidPath { path } => {
+                out.write_fmt(format_args!("{}{}: path is invalid\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::NoSuchItem { path } => {
+                out.write_fmt(format_args!("{}{}: there was nothing there\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::InsufficientInventory {
+                path,
+                requested,
+                available,
+            } => {
+                out.write_fmt(format_args!("{}{}: Tried to move {}, but only have {}\n", <dyn Render>::tab(indent), path_string(path), requested, available))?;
+            }
+            MoveItemResult::CannotSplitContainer { path } => {
+                out.write_fmt(format_args!("{}{}: A container can only be moved whole\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::ContainerDoesNotExistFor { path } => {
+                out.write_fmt(format_args!("{}{}: The specified container does not exist and needs to be created\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::DestinationIsObject { path } => {
+                out.write_fmt(format_args!("{}{}: The destination is an object, not a container\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::DestinationOccupied { path } => {
+                out.write_fmt(format_args!("{}{}: There is already a container there\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            MoveItemResult::IntoItself { path } => {
+                out.write_fmt(format_args!("{}{}: Cannot move an item into itself\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MoveItemResult {
+    /// The path the item was moved from, if nothing was left there.
+    pub fn removed(&self) -> Option<Vec<String>> {
+        match self {
+            MoveItemResult::Success {
+                from, emptied: true, ..
+            } => Some(from.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EquipResult {
+    Success { path: Vec<String>, equipped: bool },
+    NoSuchItem { path: Vec<String> },
+    IsContainer { path: Vec<String> },
+}
+
+impl EquipResult {
+    fn with_path(&self, path: Vec<String>) -> EquipResult {
+        match self {
+            EquipResult::Success { equipped, .. } => EquipResult::Success {
+                path,
+                equipped: *equipped,
+            },
+            EquipResult::NoSuchItem { .. } => EquipResult::NoSuchItem { path },
+            EquipResult::IsContainer { .. } => EquipResult::IsContainer { path },
+        }
+    }
+}
+
+impl Render for EquipResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        match self {
+            EquipResult::Success { path, equipped: true } => {
+                out.write_fmt(format_args!("{}{}: equipped\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            EquipResult::Success { path, equipped: false } => {
+                out.write_fmt(format_args!("{}{}: unequipped\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            EquipResult::NoSuchItem { path } => {
+                out.write_fmt(format_args!("{}{}: there was nothing there\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+            EquipResult::IsContainer { path } => {
+                out.write_fmt(format_args!("{}{}: The specified path is a container\n", <dyn Render>::tab(indent), path_string(path)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Inventory {
 
     pub fn new(items: HashMap<String, InventoryItem>) -> Inventory {
@@ -269,6 +669,58 @@ impl Inventory {
         &self.items
     }
 
+    pub fn set_container_details(&mut self, path: &[String], details: ContainerDetails) -> bool {
+        let container = path
+            .split_last()
+            .and_then(|(name, parent)| self.items_mut(parent).and_then(|items| items.get_mut(name)));
+        match container {
+            Some(InventoryItem::Container { details: existing, .. }) => {
+                *existing = details;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The weight of everything in the inventory.
+    pub fn total_weight(&self) -> Weight {
+        self.items.values().map(|item| item.total_weight()).sum()
+    }
+
+    /// A copy of the inventory with each object's missing details filled in from its catalog entry.
+    pub fn with_catalog(&self, catalog: &HashMap<String, ItemDetails>) -> Inventory {
+        Inventory {
+            items: self
+                .items
+                .iter()
+                .map(|(name, item)| (name.clone(), item.with_catalog(catalog)))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, path: &[String]) -> Option<&InventoryItem> {
+        path.split_first()
+            .and_then(|(name, child_path)| self.items.get(name).and_then(|item| item.get(child_path)))
+    }
+
+    pub fn equipped(&self) -> Vec<(Vec<String>, &InventoryItem)> {
+        self.items
+            .iter()
+            .sorted_by_key(|(name, _)| name.to_string())
+            .flat_map(|(name, item)| item.equipped(vec![name.clone()]))
+            .collect()
+    }
+
+    pub fn set_equipped(&mut self, path: Vec<String>, equip: bool) -> EquipResult {
+        match path.split_first() {
+            Some((name, child_path)) => match self.items.get_mut(name) {
+                Some(item) => item.set_equipped(child_path.to_vec(), equip).with_path(path),
+                None => EquipResult::NoSuchItem { path },
+            },
+            None => EquipResult::NoSuchItem { path },
+        }
+    }
+
     pub fn add_item(&mut self, path: Vec<String>, count: isize) -> Result<AddItemResult> {
         if let Some(name) = path.first() {
             let child_path = path
@@ -286,7 +738,7 @@ impl Inventory {
                     let child_path = child_path.clone();
                     if child_path.is_empty() {
                         match inventory_item {
-                            InventoryItem::Object { count } => {
+                            InventoryItem::Object { count, .. } => {
                                 if count.clone() + requested < 0 {
                                     Ok(AddItemResult::InsufficientInventory {
                                         path,
@@ -313,7 +765,7 @@ impl Inventory {
                     let path = path.clone();
                     if (child_path.clone().is_empty()) {
                         self.items
-                            .insert(name.clone(), InventoryItem::Object { count });
+                            .insert(name.clone(), InventoryItem::object(count));
                         Ok(AddItemResult::Success {
                             path,
                             requested: count,
@@ -338,6 +790,147 @@ impl Inventory {
         }
     }
 
+    /// The items directly in the container at `path`, or at the top level for an empty path.
+    fn items_mut(&mut self, path: &[String]) -> Option<&mut HashMap<String, InventoryItem>> {
+        match path.split_first() {
+            None => Some(&mut self.items),
+            Some((name, child_path)) => self.items.get_mut(name).and_then(|item| item.items_mut(child_path)),
+        }
+    }
+
+    /// Moves `count` of the object at `from`, or all of it or a whole container when `count` is
+    /// `None`. When `to` is a container the item keeps its name inside it. Otherwise `to` must end
+    /// in the item's name, unless `rename` is set, in which case `to` is the item's new path. A stack
+    /// moved onto an identical object is merged with it.
+    pub fn move_item(&mut self, from: Vec<String>, to: Vec<String>, count: Option<isize>, rename: bool) -> MoveItemResult {
+        let (name, from_parent) = match from.split_last() {
+            Some(split) => split,
+            None => return MoveItemResult::InvalidPath { path: from },
+        };
+        let item = match self.get(&from) {
+            Some(item) => item.clone(),
+            None => return MoveItemResult::NoSuchItem { path: from },
+        };
+        let (moved, remaining) = match (&item, count) {
+            (InventoryItem::Container { .. }, Some(_)) => return MoveItemResult::CannotSplitContainer { path: from },
+            (InventoryItem::Container { .. }, None) => (item.clone(), None),
+            (InventoryItem::Object { count: available, .. }, count) => {
+                let requested = count.unwrap_or(*available);
+                if requested <= 0 || requested > *available {
+                    return MoveItemResult::InsufficientInventory {
+                        path: from,
+                        requested,
+                        available: *available,
+                    };
+                }
+                (item.with_count(requested), Some(available - requested).filter(|r| *r > 0))
+            }
+        };
+
+        let target = match self.get(&to) {
+            _ if to.is_empty() => vec![name.clone()],
+            _ if rename => to.clone(),
+            Some(InventoryItem::Container { .. }) => to.iter().chain(Some(name)).cloned().collect(),
+            _ if to.last() == Some(name) => to.clone(),
+            _ => return MoveItemResult::ContainerDoesNotExistFor { path: to },
+        };
+        if target.starts_with(&from) {
+            return MoveItemResult::IntoItself { path: from };
+        }
+        let (target_name, target_parent) = target.split_last().unwrap();
+        if self.items_mut(target_parent).is_none() {
+            return match self.get(target_parent) {
+                Some(InventoryItem::Object { .. }) => MoveItemResult::DestinationIsObject {
+                    path: target_parent.to_vec(),
+                },
+                _ => MoveItemResult::ContainerDoesNotExistFor { path: target },
+            };
+        }
+        let merged = match self.get(&target) {
+            None => moved.clone(),
+            Some(existing @ InventoryItem::Object { count: existing_count, .. })
+                if existing.with_count(0) == moved.with_count(0) =>
+            {
+                moved.with_count(existing_count + moved.count())
+            }
+            Some(InventoryItem::Object { .. }) => return MoveItemResult::DestinationIsObject { path: target },
+            Some(InventoryItem::Container { .. }) => return MoveItemResult::DestinationOccupied { path: target },
+        };
+
+        let source = self.items_mut(from_parent).unwrap();
+        let emptied = remaining.is_none();
+        match remaining {
+            Some(remaining) => {
+                source.insert(name.clone(), item.with_count(remaining));
+            }
+            None => {
+                source.remove(name);
+            }
+        }
+        self.items_mut(target_parent)
+            .unwrap()
+            .insert(target_name.clone(), merged);
+        MoveItemResult::Success {
+            from,
+            to: target,
+            count: if moved.is_container() { None } else { Some(moved.count()) },
+            emptied,
+        }
+    }
+
+    pub fn remove_container(&mut self, path: Vec<String>, mode: RemoveContainerMode) -> RemoveContainerResult {
+        let (name, parent_path) = match path.split_last() {
+            Some(split) => split,
+            None => return RemoveContainerResult::PathIsEmpty { path },
+        };
+        let parent = match self.items_mut(parent_path) {
+            Some(parent) => parent,
+            None => return RemoveContainerResult::NoSuchContainer { path },
+        };
+        let contents = match parent.get(name) {
+            Some(InventoryItem::Container { items, .. }) => items.clone(),
+            Some(InventoryItem::Object { .. }) => return RemoveContainerResult::ExpectedContainer { path },
+            None => return RemoveContainerResult::NoSuchContainer { path },
+        };
+        let names = contents.keys().cloned().sorted().collect::<Vec<String>>();
+
+        match mode {
+            RemoveContainerMode::IfEmpty if !contents.is_empty() => {
+                return RemoveContainerResult::NotEmpty {
+                    count: contents.len(),
+                    path,
+                }
+            }
+            RemoveContainerMode::Spill => {
+                let collisions = names
+                    .iter()
+                    .filter(|child| *child != name && parent.contains_key(*child))
+                    .cloned()
+                    .collect::<Vec<String>>();
+                if !collisions.is_empty() {
+                    return RemoveContainerResult::Collision {
+                        path,
+                        names: collisions,
+                    };
+                }
+            }
+            _ => {}
+        }
+
+        parent.remove(name);
+        let (spilled, discarded) = if mode == RemoveContainerMode::Spill {
+            parent.extend(contents);
+            (names, vec![])
+        } else {
+            (vec![], names)
+        };
+        RemoveContainerResult::Success {
+            path: path.clone(),
+            spilled,
+            discarded,
+        }
+    }
+
     pub fn add_container(&mut self, path: Vec<String>) -> Result<AddContainerResult> {
         if let Some(first) = path.first() {
             let child_path = path
@@ -357,7 +950,7 @@ impl Inventory {
                 },
                 None => {
                     if(child_path.is_empty()) {
-                        self.items.insert(first.clone(), InventoryItem::Container { items: HashMap::new() });
+                        self.items.insert(first.clone(), InventoryItem::container(HashMap::new()));
                         Ok(AddContainerResult::Success { path })
                     } else {
                         Ok(AddContainerResult::NoSuchParent { path })
@@ -375,13 +968,206 @@ impl Inventory {
 pub enum InventoryItem {
     Object {
         count: isize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weapon: Option<Weapon>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        armor: Option<Armor>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        equipped: bool,
+        /// Effects that apply while the item is equipped.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        effects: Vec<Effect>,
+        #[serde(default, flatten, skip_serializing_if = "ItemDetails::is_empty")]
+        details: Box<ItemDetails>,
+        /// The catalog entry that provides any missing details.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        catalog: Option<String>,
     },
     Container {
         items: HashMap<String, InventoryItem>,
+        #[serde(default, skip_serializing_if = "ContainerDetails::is_empty")]
+        details: ContainerDetails,
     },
 }
 
 impl InventoryItem {
+    pub fn object(count: isize) -> InventoryItem {
+        InventoryItem::Object {
+            count,
+            weapon: None,
+            armor: None,
+            equipped: false,
+            effects: vec![],
+            details: Box::default(),
+            catalog: None,
+        }
+    }
+
+    pub fn container(items: HashMap<String, InventoryItem>) -> InventoryItem {
+        InventoryItem::Container {
+            items,
+            details: ContainerDetails::default(),
+        }
+    }
+
+    fn with_catalog(&self, catalog: &HashMap<String, ItemDetails>) -> InventoryItem {
+        let mut item = self.clone();
+        match &mut item {
+            InventoryItem::Object {
+                details,
+                catalog: Some(entry),
+                ..
+            } => {
+                if let Some(entry) = catalog.get(entry) {
+                    **details = details.or(entry);
+                }
+            }
+            InventoryItem::Object { .. } => {}
+            InventoryItem::Container { items, .. } => {
+                *items = items
+                    .iter()
+                    .map(|(name, item)| (name.clone(), item.with_catalog(catalog)))
+                    .collect();
+            }
+        }
+        item
+    }
+
+    /// The weight of a whole stack, or of a container along with everything in it that counts.
+    /// Unknown weights count as nothing.
+    pub fn total_weight(&self) -> Weight {
+        match self {
+            InventoryItem::Object { count, details, .. } => details.weight.unwrap_or_default().times(*count),
+            InventoryItem::Container { details, .. } if details.weightless_contents => {
+                details.weight.unwrap_or_default()
+            }
+            InventoryItem::Container { items, details } => {
+                details.weight.unwrap_or_default() + items.values().map(|item| item.total_weight()).sum()
+            }
+        }
+    }
+
+    /// The value in copper of a whole stack, or of everything in a container.
+    pub fn total_value(&self) -> isize {
+        match self {
+            InventoryItem::Object { count, details, .. } => {
+                details.cost.as_ref().map_or(0, |cost| cost.in_copper() * count)
+            }
+            InventoryItem::Container { items, .. } => items.values().map(|item| item.total_value()).sum(),
+        }
+    }
+
+    fn label(&self, name: &str) -> String {
+        let count = self.count();
+        let details = self.details();
+        if details.is_empty() {
+            format!("x{} ... {}", count, name)
+        } else {
+            format!("x{} ... {} ({})", count, name, details.join(", "))
+        }
+    }
+
+    fn is_container(&self) -> bool {
+        matches!(self, InventoryItem::Container { .. })
+    }
+
+    fn count(&self) -> isize {
+        match self {
+            InventoryItem::Object { count, .. } => *count,
+            InventoryItem::Container { .. } => 0,
+        }
+    }
+
+    /// A copy of an object with a different count, such as part of a split stack.
+    fn with_count(&self, count: isize) -> InventoryItem {
+        let mut item = self.clone();
+        if let InventoryItem::Object { count: c, .. } = &mut item {
+            *c = count;
+        }
+        item
+    }
+
+    fn details(&self) -> Vec<String> {
+        match self {
+            InventoryItem::Object {
+                weapon,
+                armor,
+                equipped,
+                ..
+            } => {
+                let mut details = vec![];
+                details.extend(weapon.iter().map(|weapon| weapon.to_string()));
+                details.extend(armor.iter().map(|armor| armor.to_string()));
+                if *equipped {
+                    details.push("equipped".to_string());
+                }
+                details
+            }
+            InventoryItem::Container { .. } => vec![],
+        }
+    }
+
+    /// Every equipped object in or below this item, along with its path.
+    fn equipped(&self, path: Vec<String>) -> Vec<(Vec<String>, &InventoryItem)> {
+        match self {
+            InventoryItem::Object { equipped: true, .. } => vec![(path, self)],
+            InventoryItem::Object { .. } => vec![],
+            InventoryItem::Container { items, .. } => items
+                .iter()
+                .sorted_by_key(|(name, _)| name.to_string())
+                .flat_map(|(name, item)| {
+                    let mut path = path.clone();
+                    path.push(name.clone());
+                    item.equipped(path)
+                })
+                .collect(),
+        }
+    }
+
+    fn set_equipped(&mut self, path: Vec<String>, equip: bool) -> EquipResult {
+        match path.split_first() {
+            Some((name, child_path)) => match self {
+                InventoryItem::Container { items, .. } => match items.get_mut(name) {
+                    Some(item) => item.set_equipped(child_path.to_vec(), equip),
+                    None => EquipResult::NoSuchItem { path: vec![] },
+                },
+                InventoryItem::Object { .. } => EquipResult::NoSuchItem { path: vec![] },
+            },
+            None => match self {
+                InventoryItem::Object { equipped, .. } => {
+                    *equipped = equip;
+                    EquipResult::Success {
+                        path: vec![],
+                        equipped: equip,
+                    }
+                }
+                InventoryItem::Container { .. } => EquipResult::IsContainer { path: vec![] },
+            },
+        }
+    }
+
+    fn items_mut(&mut self, path: &[String]) -> Option<&mut HashMap<String, InventoryItem>> {
+        match self {
+            InventoryItem::Container { items, .. } => match path.split_first() {
+                None => Some(items),
+                Some((name, child_path)) => items.get_mut(name).and_then(|item| item.items_mut(child_path)),
+            },
+            InventoryItem::Object { .. } => None,
+        }
+    }
+
+    pub fn get(&self, path: &[String]) -> Option<&InventoryItem> {
+        match path.split_first() {
+            None => Some(self),
+            Some((name, child_path)) => match self {
+                InventoryItem::Container { items, .. } => {
+                    items.get(name).and_then(|item| item.get(child_path))
+                }
+                InventoryItem::Object { .. } => None,
+            },
+        }
+    }
+
     pub fn add_item(&mut self, path: Vec<String>, count: isize) -> Result<AddItemResult> {
         if let Some(first) = path.first() {
             let child_path = path
@@ -393,7 +1179,7 @@ impl InventoryItem {
 
             match self {
                 InventoryItem::Object { .. } => Ok(AddItemResult::ObjectAtSubpath { path: path }),
-                InventoryItem::Container { items } => {
+                InventoryItem::Container { items, .. } => {
                     let result = items
                         .get_mut(first)
                         .map(|item| item.add_item(child_path.clone(), requested))
@@ -402,7 +1188,7 @@ impl InventoryItem {
                             if (child_path.clone().is_empty()) {
                                 items.insert(
                                     first.clone(),
-                                    InventoryItem::Object { count: requested },
+                                    InventoryItem::object(requested),
                                 );
                                 Ok(AddItemResult::Success {
                                     path,
@@ -432,7 +1218,7 @@ impl InventoryItem {
         } else {
             let requested = count;
             match self {
-                InventoryItem::Object { count } => {
+                InventoryItem::Object { count, .. } => {
                     if count.clone() + requested < 0 {
                         Ok(AddItemResult::InsufficientInventory {
                             path,
@@ -457,13 +1243,13 @@ impl InventoryItem {
 
     fn add_container(&mut self, path: Vec<String>) -> Result<AddContainerResult> {
         match self {
-            InventoryItem::Container { items } => {
+            InventoryItem::Container { items, .. } => {
                 if let Some(name) = path.first() {
                     let child_path = path.iter().skip(1).map(|s| s.clone()).collect::<Vec<String>>();
                     if(child_path.is_empty()) {
                         match items.get(name) {
                             None => {
-                                items.insert(name.clone(), InventoryItem::Container { items: HashMap::new() });
+                                items.insert(name.clone(), InventoryItem::container(HashMap::new()));
                                 Ok(AddContainerResult::Success { path })
                             },
                             _ => Ok(AddContainerResult::Collision { path })
@@ -482,4 +1268,148 @@ impl InventoryItem {
             InventoryItem::Object { .. } => Ok(AddContainerResult::ExpectedContainer { path })
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::{
+        Inventory, InventoryItem, ItemDetails, MoveItemResult, RemoveContainerMode, RemoveContainerResult, Weight,
+    };
+    use crate::domain::currency::Coins;
+    use std::collections::HashMap;
+
+    fn path(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_remove_container() {
+        let pouch = InventoryItem::container(vec![("gem".to_string(), InventoryItem::object(3))].into_iter().collect());
+        let mut inventory = Inventory {
+            items: vec![
+                ("pouch".to_string(), pouch.clone()),
+                ("gem".to_string(), InventoryItem::object(1)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        assert_eq!(
+            inventory.remove_container(path(&["pouch"]), RemoveContainerMode::IfEmpty),
+            RemoveContainerResult::NotEmpty { path: path(&["pouch"]), count: 1 }
+        );
+        assert_eq!(
+            inventory.remove_container(path(&["pouch"]), RemoveContainerMode::Spill),
+            RemoveContainerResult::Collision { path: path(&["pouch"]), names: path(&["gem"]) }
+        );
+        assert_eq!(
+            inventory.remove_container(path(&["gem"]), RemoveContainerMode::Discard),
+            RemoveContainerResult::ExpectedContainer { path: path(&["gem"]) }
+        );
+        let result = inventory.remove_container(path(&["pouch"]), RemoveContainerMode::Discard);
+        assert_eq!(
+            result,
+            RemoveContainerResult::Success {
+                path: path(&["pouch"]),
+                spilled: vec![],
+                discarded: path(&["gem"]),
+            }
+        );
+        assert_eq!(result.removed(), Some(path(&["pouch"])));
+        assert_eq!(inventory.items.get("gem"), Some(&InventoryItem::object(1)));
+
+        inventory.items = HashMap::from([("pouch".to_string(), pouch)]);
+        inventory.remove_container(path(&["pouch"]), RemoveContainerMode::Spill);
+        assert_eq!(inventory.items, HashMap::from([("gem".to_string(), InventoryItem::object(3))]));
+    }
+
+    #[test]
+    fn test_move_item() {
+        let mut inventory = Inventory {
+            items: HashMap::from([
+                ("bag".to_string(), InventoryItem::container(HashMap::new())),
+                ("rope".to_string(), InventoryItem::object(10)),
+            ]),
+        };
+
+        assert_eq!(
+            inventory.move_item(path(&["rope"]), path(&["bga"]), Some(4), false),
+            MoveItemResult::ContainerDoesNotExistFor { path: path(&["bga"]) }
+        );
+        let result = inventory.move_item(path(&["rope"]), path(&["bag"]), Some(4), false);
+        assert_eq!(
+            result,
+            MoveItemResult::Success { from: path(&["rope"]), to: path(&["bag", "rope"]), count: Some(4), emptied: false }
+        );
+        assert_eq!(result.removed(), None);
+        let result = inventory.move_item(path(&["rope"]), path(&["bag", "rope"]), None, false);
+        assert_eq!(
+            result,
+            MoveItemResult::Success { from: path(&["rope"]), to: path(&["bag", "rope"]), count: Some(6), emptied: true }
+        );
+        assert_eq!(result.removed(), Some(path(&["rope"])));
+        assert_eq!(inventory.get(&path(&["bag", "rope"])), Some(&InventoryItem::object(10)));
+        assert_eq!(inventory.get(&path(&["rope"])), None);
+        assert_eq!(
+            inventory.move_item(path(&["bag"]), path(&["bag"]), None, false),
+            MoveItemResult::IntoItself { path: path(&["bag"]) }
+        );
+        assert_eq!(
+            inventory.move_item(path(&["bag"]), path(&["bag", "rope"]), None, true),
+            MoveItemResult::IntoItself { path: path(&["bag"]) }
+        );
+        assert_eq!(
+            inventory.move_item(path(&["bag"]), path(&["pack"]), Some(1), true),
+            MoveItemResult::CannotSplitContainer { path: path(&["bag"]) }
+        );
+        assert_eq!(
+            inventory.move_item(path(&["bag"]), path(&["pack"]), None, false),
+            MoveItemResult::ContainerDoesNotExistFor { path: path(&["pack"]) }
+        );
+        assert_eq!(
+            inventory.move_item(path(&["bag"]), path(&["pack"]), None, true),
+            MoveItemResult::Success { from: path(&["bag"]), to: path(&["pack"]), count: None, emptied: true }
+        );
+        assert_eq!(inventory.get(&path(&["pack", "rope"])), Some(&InventoryItem::object(10)));
+    }
+
+    #[test]
+    fn test_catalog() {
+        let item: InventoryItem =
+            serde_json::from_str(r#"{"type": "Object", "count": 10, "catalog": "arrow", "tags": ["silvered"]}"#).unwrap();
+        let catalog = HashMap::from([(
+            "arrow".to_string(),
+            ItemDetails {
+                weight: Some(Weight::from_hundredths(5)),
+                cost: Some(Coins { cp: 5, ..Coins::default() }),
+                description: None,
+                tags: vec!["ammunition".to_string()],
+            },
+        )]);
+        let inventory = Inventory {
+            items: HashMap::from([("arrows".to_string(), item)]),
+        }
+        .with_catalog(&catalog);
+
+        let arrows = inventory.get(&path(&["arrows"])).unwrap();
+        assert_eq!(arrows.total_weight().to_string(), "0.5 lb");
+        assert_eq!(arrows.total_value(), 50);
+        match arrows {
+            InventoryItem::Object { details, .. } => assert_eq!(details.tags, vec!["silvered".to_string()]),
+            InventoryItem::Container { .. } => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn test_weight() {
+        let weight: Weight = serde_json::from_str("0.05").unwrap();
+        assert_eq!(weight, Weight::from_hundredths(5));
+        assert_eq!(weight.times(3).to_string(), "0.15 lb");
+        assert_eq!(weight.times(20).to_string(), "1 lb");
+        assert_eq!(serde_json::to_string(&weight).unwrap(), "0.05");
+
+        assert!(Weight::from_pounds(f64::NAN).is_err());
+        assert!(Weight::from_pounds(f64::INFINITY).is_err());
+        assert!(Weight::from_pounds(-1.0).is_err());
+        assert!(serde_json::from_str::<ItemDetails>(r#"{"weight": -2}"#).is_err());
+    }
+}
@@ -1,6 +1,19 @@
 pub mod ability_score;
+pub mod armor_class;
 pub mod character;
+pub mod concentration;
+pub mod condition;
+pub mod currency;
+pub mod damage;
 pub mod effect;
+pub mod encumbrance;
 pub mod inventory;
+pub mod resource;
+pub mod rest;
 pub mod roll;
+pub mod spell;
+pub mod spell_slots;
+pub mod speed;
+pub mod hit_dice;
 pub mod hit_points;
+pub mod weapon;
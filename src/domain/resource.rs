@@ -0,0 +1,204 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::rest::Rest;
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+/// A limited number of uses granted by a feature, such as ki points or uses of rage.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Resource {
+    pub maximum: ResourceMaximum,
+    pub recharge: Recharge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum ResourceMaximum {
+    Fixed {
+        value: isize,
+    },
+    /// Looked up by the level of `class`, or by total level without one. The first entry is the
+    /// maximum at level 1.
+    PerLevel {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        class: Option<String>,
+        table: Vec<isize>,
+    },
+    /// The modifier of an ability, such as Bardic Inspiration's Charisma modifier, but at least
+    /// `minimum` (1 by default).
+    AbilityModifier {
+        ability: Ability,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        minimum: Option<isize>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Recharge {
+    ShortRest,
+    LongRest,
+    /// Regained at dawn, which is treated as the end of a long rest.
+    Dawn,
+}
+
+impl Recharge {
+    /// Whether this recharges at the end of `rest`. A long rest recharges everything.
+    pub fn on(&self, rest: &Rest) -> bool {
+        match rest {
+            Rest::Short => self == &Recharge::ShortRest,
+            Rest::Long => true,
+        }
+    }
+}
+
+impl Display for Recharge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recharge::ShortRest => f.write_str("short rest"),
+            Recharge::LongRest => f.write_str("long rest"),
+            Recharge::Dawn => f.write_str("dawn"),
+        }
+    }
+}
+
+impl Resource {
+    pub fn maximum(&self, character: &Character) -> isize {
+        match &self.maximum {
+            ResourceMaximum::Fixed { value } => *value,
+            ResourceMaximum::PerLevel { class, table } => {
+                let level = match class {
+                    Some(class) => character.class_level(class),
+                    None => character.total_level(),
+                };
+                if level < 1 || table.is_empty() {
+                    0
+                } else {
+                    table[min(level as usize, table.len()) - 1]
+                }
+            }
+            ResourceMaximum::AbilityModifier { ability, minimum } => max(
+                minimum.unwrap_or(1),
+                character.get_ability_score(ability.clone()).modifier(),
+            ),
+        }
+    }
+}
+
+/// Uses spent of each resource, by name. The resources themselves are declared by features.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct Resources {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    used: BTreeMap<String, isize>,
+}
+
+impl Resources {
+    pub fn used(&self, name: &str) -> isize {
+        self.used.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn spend(&mut self, name: &str, count: isize, maximum: isize) -> Result<()> {
+        let remaining = max(0, maximum - self.used(name));
+        if count > remaining {
+            return Err(anyhow!(
+                "Cannot use {} of {}, only {} remaining",
+                count,
+                name,
+                remaining
+            ));
+        }
+        *self.used.entry(name.to_string()).or_insert(0) += count;
+        Ok(())
+    }
+
+    /// Regains `count` uses, or every use without a count. Returns the number of uses regained.
+    pub fn regain(&mut self, name: &str, count: Option<isize>) -> isize {
+        let used = self.used(name);
+        let regained = min(used, count.unwrap_or(used));
+        if used - regained > 0 {
+            self.used.insert(name.to_string(), used - regained);
+        } else {
+            self.used.remove(name);
+        }
+        regained
+    }
+}
+
+/// The state of a single resource.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResourceResult {
+    name: String,
+    path: Vec<String>,
+    recharge: Recharge,
+    remaining: isize,
+    maximum: isize,
+}
+
+impl ResourceResult {
+    pub fn of(character: &Character, name: &str) -> Result<ResourceResult> {
+        ResourceResult::all(character)
+            .into_iter()
+            .find(|result| result.name == name)
+            .ok_or_else(|| anyhow!("No resource named {}", name))
+    }
+
+    pub fn all(character: &Character) -> Vec<ResourceResult> {
+        character
+            .all_resources()
+            .into_iter()
+            .map(|(path, name, resource)| {
+                let maximum = resource.maximum(character);
+                ResourceResult {
+                    remaining: max(0, maximum - character.resources_used(&name)),
+                    name,
+                    path,
+                    recharge: resource.recharge,
+                    maximum,
+                }
+            })
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect()
+    }
+
+    pub fn maximum(&self) -> isize {
+        self.maximum
+    }
+}
+
+impl Render for ResourceResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!(
+            "{}{}: {} / {} (recharges on {}, from {})\n",
+            <dyn Render>::tab(indent),
+            self.name,
+            self.remaining,
+            self.maximum,
+            self.recharge,
+            self.path.iter().join(" / ")
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Resources;
+
+    #[test]
+    fn test_spend_and_regain() {
+        let mut resources = Resources::default();
+        resources.spend("ki", 3, 4).unwrap();
+        assert!(resources.spend("ki", 2, 4).is_err());
+        assert_eq!(resources.used("ki"), 3);
+
+        assert_eq!(resources.regain("ki", Some(1)), 1);
+        assert_eq!(resources.regain("ki", None), 2);
+        assert_eq!(resources.regain("ki", None), 0);
+        assert_eq!(resources, Resources::default());
+    }
+}
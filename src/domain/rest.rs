@@ -0,0 +1,418 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::effect::RollBonus;
+use crate::domain::roll::{Dice, Roll, RollContext, RollResult};
+use crate::domain::spell_slots::ordinal;
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use std::cmp::max;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Rest {
+    Short,
+    Long,
+}
+
+impl Display for Rest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} Rest", self)
+    }
+}
+
+/// A single thing recovered during a rest.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Recovery {
+    HitDie { sides: isize, roll: RollResult },
+    HitPoints { from: isize, to: isize },
+    TemporaryHitPoints { lost: isize },
+    HitDice { regained: BTreeMap<isize, isize> },
+    DeathSaves,
+    Resource { name: String, regained: isize },
+    SpellSlots { regained: BTreeMap<isize, isize> },
+    PactSlots { regained: isize },
+    Condition { name: String, level: isize },
+}
+
+/// Everything a character recovered during a rest.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RestResult {
+    rest: Rest,
+    recoveries: Vec<Recovery>,
+    hit_dice_remaining: BTreeMap<isize, isize>,
+}
+
+/// Spends `count` hit dice, of the given number of sides or the largest remaining ones. Each die
+/// is rolled with the character's Constitution modifier and heals for the total. Resources that
+/// recharge on a short rest and pact slots are regained.
+pub fn short_rest(character: &mut Character, count: isize, sides: Option<isize>) -> Result<RestResult> {
+    if character.hit_points().is_dead() {
+        return Err(anyhow!("The dead cannot rest"));
+    }
+    let total = character.total_hit_dice();
+    let remaining = character.hit_dice().remaining(&total);
+    let available: isize = match sides {
+        Some(sides) => remaining.get(&sides).copied().unwrap_or(0),
+        None => remaining.values().sum(),
+    };
+    if count > available {
+        return Err(anyhow!(
+            "Cannot spend {} hit dice, only {} remaining",
+            count,
+            available
+        ));
+    }
+
+    let before = character.hit_points().current();
+    let mut recoveries = vec![];
+    for _ in 0..count {
+        let sides = character
+            .hit_dice()
+            .spend(&total, sides)
+            .ok_or_else(|| anyhow!("No hit dice remaining"))?;
+        let path = vec!["hit-dice".to_string(), format!("d{}", sides)];
+        let roll = Roll::calculate_with(
+            &path,
+            &RollContext::default(),
+            character,
+            vec![
+                (
+                    vec!["hit-dice".to_string()],
+                    RollBonus::Roll(Roll::new(vec![Dice::new(1, sides)])),
+                ),
+                (
+                    vec!["hit-dice".to_string()],
+                    RollBonus::Ability(Ability::Constitution),
+                ),
+            ],
+        );
+        character.hit_points().add_current(max(0, roll.total()));
+        recoveries.push(Recovery::HitDie { sides, roll });
+    }
+    if count > 0 {
+        recoveries.push(Recovery::HitPoints {
+            from: before,
+            to: character.hit_points().current(),
+        });
+    }
+
+    recoveries.extend(recover_resources(character, &Rest::Short));
+    recoveries.extend(recover_pact_slots(character));
+
+    Ok(RestResult {
+        rest: Rest::Short,
+        recoveries,
+        hit_dice_remaining: character.hit_dice().remaining(&total),
+    })
+}
+
+/// Restores hit points to their maximum, loses temporary hit points, clears death saves and
+/// regains half of the character's total hit dice, rounded down but at least one. Every resource
+/// and spell slot is regained as well, and conditions such as exhaustion are reduced.
+pub fn long_rest(character: &mut Character) -> Result<RestResult> {
+    if character.hit_points().is_dead() {
+        return Err(anyhow!("The dead cannot rest"));
+    }
+    let total = character.total_hit_dice();
+    let mut recoveries = recover_conditions(character)?;
+
+    let hit_points = character.hit_points();
+    let (from, temporary, had_death_saves) = (
+        hit_points.current(),
+        hit_points.temporary(),
+        hit_points.has_death_saves(),
+    );
+    hit_points.reset();
+    if hit_points.current() != from {
+        recoveries.push(Recovery::HitPoints {
+            from,
+            to: hit_points.current(),
+        });
+    }
+    if temporary > 0 {
+        recoveries.push(Recovery::TemporaryHitPoints { lost: temporary });
+    }
+    if had_death_saves {
+        recoveries.push(Recovery::DeathSaves);
+    }
+
+    let regained = character
+        .hit_dice()
+        .regain(max(1, total.values().sum::<isize>() / 2));
+    if !regained.is_empty() {
+        recoveries.push(Recovery::HitDice { regained });
+    }
+
+    recoveries.extend(recover_resources(character, &Rest::Long));
+    let regained = character.spell_slots().regain(None);
+    if !regained.is_empty() {
+        recoveries.push(Recovery::SpellSlots { regained });
+    }
+    recoveries.extend(recover_pact_slots(character));
+
+    Ok(RestResult {
+        rest: Rest::Long,
+        recoveries,
+        hit_dice_remaining: character.hit_dice().remaining(&total),
+    })
+}
+
+fn recover_resources(character: &mut Character, rest: &Rest) -> Vec<Recovery> {
+    character
+        .all_resources()
+        .into_iter()
+        .filter(|(_, _, resource)| resource.recharge.on(rest))
+        .flat_map(|(_, name, _)| {
+            let regained = character.resources().regain(&name, None);
+            if regained > 0 {
+                Some(Recovery::Resource { name, regained })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Conditions that recover on a long rest lose a level, before hit points are restored so that a
+/// maximum reduced by them is restored as well.
+fn recover_conditions(character: &mut Character) -> Result<Vec<Recovery>> {
+    let active = character.active_conditions().active().clone();
+    let mut recoveries = vec![];
+    for name in active.keys() {
+        let condition = character.condition(name).unwrap_or_default();
+        if condition.long_rest_recovery {
+            let level = character.remove_condition(name)?;
+            recoveries.push(Recovery::Condition {
+                name: name.clone(),
+                level,
+            });
+        }
+    }
+    Ok(recoveries)
+}
+
+fn recover_pact_slots(character: &mut Character) -> Option<Recovery> {
+    let regained = character.spell_slots().regain_pact();
+    if regained > 0 {
+        Some(Recovery::PactSlots { regained })
+    } else {
+        None
+    }
+}
+
+fn format_hit_dice(hit_dice: &BTreeMap<isize, isize>) -> String {
+    hit_dice
+        .iter()
+        .rev()
+        .map(|(sides, count)| format!("{}d{}", count, sides))
+        .join(", ")
+}
+
+impl Render for Recovery {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let tab = <dyn Render>::tab(indent);
+        match self {
+            Recovery::HitDie { sides, roll } => {
+                out.write_fmt(format_args!("{}Hit Die (d{})\n", tab, sides))?;
+                roll.render(indent + 1, out)?;
+            }
+            Recovery::HitPoints { from, to } => {
+                out.write_fmt(format_args!("{}Hit Points: {} → {}\n", tab, from, to))?;
+            }
+            Recovery::TemporaryHitPoints { lost } => {
+                out.write_fmt(format_args!("{}Temporary Hit Points lost: {}\n", tab, lost))?;
+            }
+            Recovery::HitDice { regained } => {
+                out.write_fmt(format_args!(
+                    "{}Hit Dice regained: {}\n",
+                    tab,
+                    format_hit_dice(regained)
+                ))?;
+            }
+            Recovery::DeathSaves => {
+                out.write_fmt(format_args!("{}Death saves reset\n", tab))?;
+            }
+            Recovery::Resource { name, regained } => {
+                out.write_fmt(format_args!("{}{} regained: {}\n", tab, name, regained))?;
+            }
+            Recovery::SpellSlots { regained } => {
+                out.write_fmt(format_args!(
+                    "{}Spell Slots regained: {}\n",
+                    tab,
+                    regained
+                        .iter()
+                        .map(|(level, count)| format!("{} {}", count, ordinal(*level)))
+                        .join(", ")
+                ))?;
+            }
+            Recovery::PactSlots { regained } => {
+                out.write_fmt(format_args!("{}Pact Slots regained: {}\n", tab, regained))?;
+            }
+            Recovery::Condition { name, level: 0 } => {
+                out.write_fmt(format_args!("{}No longer {}\n", tab, name))?;
+            }
+            Recovery::Condition { name, level } => {
+                out.write_fmt(format_args!("{}{} reduced to level {}\n", tab, name, level))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Render for RestResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}{}\n", <dyn Render>::tab(indent), self.rest))?;
+        for recovery in &self.recoveries {
+            recovery.render(indent + 1, out)?;
+        }
+        out.write_fmt(format_args!(
+            "{}Hit Dice: {}\n",
+            <dyn Render>::tab(indent + 1),
+            format_hit_dice(&self.hit_dice_remaining)
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{long_rest, short_rest, Recovery};
+    use crate::domain::character::{test::character, Character};
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    /// A 5th level fighter with 10 of 40 hit points, with any field replaced by `overrides`.
+    fn fighter(overrides: Value) -> Character {
+        let mut fighter = json!({
+            "hit_points": { "current": 10, "max": 40, "temporary": 0 },
+            "classes": [{ "name": "Fighter", "level": 5 }],
+        });
+        fighter
+            .as_object_mut()
+            .unwrap()
+            .extend(overrides.as_object().unwrap().clone());
+        character(fighter)
+    }
+
+    fn regained(recoveries: &[Recovery]) -> Option<BTreeMap<isize, isize>> {
+        recoveries.iter().find_map(|recovery| match recovery {
+            Recovery::HitDice { regained } => Some(regained.clone()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_hit_dice() {
+        let mut veteran = fighter(json!({ "hit_dice": { "spent": { "10": 5 } } }));
+        let rest = long_rest(&mut veteran).unwrap();
+        assert_eq!(regained(&rest.recoveries), Some(vec![(10, 2)].into_iter().collect()));
+        assert_eq!(rest.hit_dice_remaining, vec![(10, 2)].into_iter().collect());
+
+        let mut novice = fighter(json!({
+            "classes": [{ "name": "Fighter", "level": 1 }],
+            "hit_dice": { "spent": { "10": 1 } },
+        }));
+        let rest = long_rest(&mut novice).unwrap();
+        assert_eq!(regained(&rest.recoveries), Some(vec![(10, 1)].into_iter().collect()));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let halved = json!({ "type": "HitPointMaximum", "bonus": { "type": "Halved" } });
+        let mut exhausted = fighter(json!({
+            "conditions": {
+                "exhaustion": {
+                    "levels": [[], [], [], [halved]],
+                    "long_rest_recovery": true,
+                },
+                "charmed": { "long_rest_recovery": true },
+                "poisoned": {},
+            },
+            "active_conditions": { "exhaustion": 4, "charmed": 1, "poisoned": 1 },
+        }));
+        exhausted.apply_hit_point_effects();
+        assert_eq!(exhausted.hit_points().get_modified_max(), 20);
+
+        let rest = long_rest(&mut exhausted).unwrap();
+        // Conditions are reduced before hit points are restored, so the maximum is no longer
+        // halved by the time they are.
+        assert_eq!(
+            rest.recoveries[..3],
+            [
+                Recovery::Condition { name: "charmed".to_string(), level: 0 },
+                Recovery::Condition { name: "exhaustion".to_string(), level: 3 },
+                Recovery::HitPoints { from: 10, to: 40 },
+            ]
+        );
+        assert_eq!(
+            exhausted.active_conditions().active(),
+            &vec![("exhaustion".to_string(), 3), ("poisoned".to_string(), 1)]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_resources_and_spell_slots() {
+        let mut caster = fighter(json!({
+            "classes": [{ "name": "Wizard", "level": 3 }],
+            "features": {
+                "Arcane Recovery": {
+                    "resources": {
+                        "Arcane Recovery": {
+                            "maximum": { "type": "Fixed", "value": 1 },
+                            "recharge": "LongRest",
+                        },
+                        "Portent": {
+                            "maximum": { "type": "Fixed", "value": 2 },
+                            "recharge": "Dawn",
+                        },
+                    },
+                },
+            },
+            "resources": { "used": { "Arcane Recovery": 1, "Portent": 2 } },
+            "spell_slots": { "used": { "1": 3, "2": 1 }, "pact_used": 1 },
+        }));
+
+        let rest = long_rest(&mut caster).unwrap();
+        assert!(rest.recoveries.contains(&Recovery::Resource {
+            name: "Arcane Recovery".to_string(),
+            regained: 1,
+        }));
+        assert!(rest.recoveries.contains(&Recovery::Resource {
+            name: "Portent".to_string(),
+            regained: 2,
+        }));
+        assert!(rest.recoveries.contains(&Recovery::SpellSlots {
+            regained: vec![(1, 3), (2, 1)].into_iter().collect(),
+        }));
+        assert!(rest.recoveries.contains(&Recovery::PactSlots { regained: 1 }));
+        assert!(long_rest(&mut caster)
+            .unwrap()
+            .recoveries
+            .iter()
+            .all(|recovery| !matches!(
+                recovery,
+                Recovery::Resource { .. } | Recovery::SpellSlots { .. } | Recovery::PactSlots { .. }
+            )));
+    }
+
+    #[test]
+    fn test_dead() {
+        let mut dead = fighter(json!({
+            "hit_points": {
+                "current": 0,
+                "max": 40,
+                "temporary": 0,
+                "death_saves": { "successes": 0, "failures": 3 },
+            },
+        }));
+        assert!(short_rest(&mut dead, 2, None).is_err());
+        assert!(long_rest(&mut dead).is_err());
+        let total = dead.total_hit_dice();
+        assert_eq!(dead.hit_dice().remaining(&total), total, "No hit dice are spent");
+    }
+}
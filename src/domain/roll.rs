@@ -1,7 +1,8 @@
 use crate::domain::character::Character;
-use crate::domain::effect::{Effect, RollBonus};
+use crate::domain::ability_score::Ability;
+use crate::domain::effect::{Advantage, Effect, Range, Reroll, RerollKeep, RollBonus};
 use crate::render::Render;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -13,28 +14,90 @@ pub struct Roll {
     dice: Vec<Dice>,
 }
 
+impl Display for Roll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dice.iter().join(" + "))
+    }
+}
+
 impl Roll {
-    pub fn calculate(path: &Vec<String>, character: &Character) -> RollResult {
-        let effects = character
-            .all_effects()
-            .iter()
-            .flat_map(|(source, effect)| match effect.clone() {
-                Effect::Roll { bonus, scope } => {
-                    if (scope.matches(path)) {
-                        Some((source.clone(), bonus))
-                    } else {
-                        None
+    pub fn new(dice: Vec<Dice>) -> Roll {
+        Roll { dice }
+    }
+
+    /// The same roll with `times` as many of each dice, such as an upcast spell's extra damage.
+    pub fn times(&self, times: isize) -> Roll {
+        Roll::new(self.dice.iter().map(|dice| dice.times(times)).collect())
+    }
+
+    pub fn calculate(path: &[String], context: &RollContext, character: &Character) -> RollResult {
+        Roll::calculate_with(path, context, character, vec![])
+    }
+
+    /// Like `calculate`, with additional bonuses that come from what is being rolled rather than
+    /// from the character, such as the damage dice of a weapon.
+    pub fn calculate_with(
+        path: &[String],
+        context: &RollContext,
+        character: &Character,
+        bonuses: Vec<(Vec<String>, RollBonus)>,
+    ) -> RollResult {
+        let mut effects = bonuses;
+        effects.extend(
+            character
+                .all_effects()
+                .iter()
+                .flat_map(|(source, effect)| match effect.clone() {
+                    Effect::Roll { bonus, scope } => {
+                        if scope.matches(path, context) {
+                            Some((source.clone(), bonus))
+                        } else {
+                            None
+                        }
                     }
-                }
+                    _ => None,
+                }),
+        );
+
+        let mut advantages = context
+            .advantages
+            .iter()
+            .map(|advantage| (vec!["requested".to_string()], advantage.clone()))
+            .collect::<Vec<(Vec<String>, Advantage)>>();
+        advantages.extend(effects.iter().flat_map(|(path, bonus)| match bonus {
+            RollBonus::Advantage(advantage) => Some((path.clone(), advantage.clone())),
+            _ => None,
+        }));
+        let advantage = Advantage::combine(
+            &advantages
+                .iter()
+                .map(|(_, advantage)| advantage.clone())
+                .collect::<Vec<Advantage>>(),
+        );
+
+        let rerolls = effects
+            .iter()
+            .flat_map(|(_, bonus)| match bonus {
+                RollBonus::Reroll(reroll) => Some(reroll.clone()),
+                _ => None,
             })
-            .collect::<Vec<(Vec<String>, RollBonus)>>();
+            .collect::<Vec<Reroll>>();
 
         let mut applicable_effects = effects
             .iter()
             .flat_map(|(path, bonus)| match bonus {
                 RollBonus::Roll(roll) => Some(EffectResult {
                     path: path.clone(),
-                    rolled_dice: roll.dice.iter().map(|d| d.roll()).collect(),
+                    rolled_dice: roll
+                        .dice
+                        .iter()
+                        .map(|d| {
+                            let dice = if context.critical { d.doubled() } else { d.clone() };
+                            let mut rolled = dice.with_advantage(&advantage).roll();
+                            rerolls.iter().for_each(|reroll| rolled.reroll(reroll));
+                            rolled
+                        })
+                        .collect(),
                     bonus: 0,
                 }),
                 RollBonus::Modifier(bonus) => Some(EffectResult {
@@ -52,37 +115,154 @@ impl Roll {
                     })
                 }
                 RollBonus::Proficiency => None,
+                RollBonus::Expertise => None,
+                RollBonus::HalfProficiency(_) => None,
+                RollBonus::Advantage(_) => None,
+                RollBonus::Reroll(_) => None,
+                RollBonus::CriticalRange(_) => None,
             })
             .collect::<Vec<EffectResult>>();
 
-        let proficiencies = effects
+        let proficiency_bonus = character.proficiency_bonus();
+        let proficiency = effects
             .iter()
-            .filter(|(path, bonus)| match bonus {
-                RollBonus::Proficiency => true,
-                _ => false,
+            .flat_map(|(path, bonus)| {
+                bonus
+                    .proficiency(proficiency_bonus)
+                    .map(|bonus| EffectResult {
+                        path: path.clone(),
+                        rolled_dice: vec![],
+                        bonus,
+                    })
             })
-            .take(1)
-            .flat_map(|(path, bonus)| match bonus {
-                RollBonus::Proficiency => Some(EffectResult {
-                    path: path.clone(),
-                    rolled_dice: vec![],
-                    bonus: character.proficiency_bonus(),
-                }),
-                _ => None,
+            .fold(None, |best: Option<EffectResult>, candidate| match best {
+                Some(best) if best.bonus >= candidate.bonus => Some(best),
+                _ => Some(candidate),
             });
 
-        applicable_effects.extend(proficiencies);
+        applicable_effects.extend(proficiency);
 
         applicable_effects.sort_by(|a, b| a.path.cmp(&b.path));
-        RollResult {
-            effects: applicable_effects,
-        }
+        let critical_range = context.critical_range.map(|critical_range| {
+            effects
+                .iter()
+                .flat_map(|(_, bonus)| match bonus {
+                    RollBonus::CriticalRange(minimum) => Some(*minimum),
+                    _ => None,
+                })
+                .fold(critical_range, isize::min)
+        });
+
+        RollResult::new(advantages, applicable_effects, critical_range)
     }
 }
 
+/// What is being rolled beyond its path, used to match effects scoped to an ability or range.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct RollContext {
+    pub ability: Option<Ability>,
+    pub range: Option<Range>,
+    /// Advantage or disadvantage requested for this roll, in addition to any granted by effects.
+    pub advantages: Vec<Advantage>,
+    /// Every die is rolled twice, as for the damage of a critical hit.
+    pub critical: bool,
+    /// The lowest natural d20 that is a critical hit, before effects lower it. Only attack rolls
+    /// can be critical hits.
+    pub critical_range: Option<isize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct RollResult {
+    #[serde(default)]
+    advantages: Vec<(Vec<String>, Advantage)>,
     effects: Vec<EffectResult>,
+    #[serde(default)]
+    critical: bool,
+}
+
+impl RollResult {
+    fn new(
+        advantages: Vec<(Vec<String>, Advantage)>,
+        effects: Vec<EffectResult>,
+        critical_range: Option<isize>,
+    ) -> RollResult {
+        let mut result = RollResult {
+            advantages,
+            effects,
+            critical: false,
+        };
+        result.critical = critical_range
+            .is_some_and(|critical_range| result.natural().is_some_and(|natural| natural >= critical_range));
+        result
+    }
+
+    /// The face of the d20 that counts, if a d20 was rolled.
+    pub fn natural(&self) -> Option<isize> {
+        self.effects
+            .iter()
+            .flat_map(|effect| effect.rolled_dice.iter())
+            .find_map(|rolled| rolled.natural())
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn total(&self) -> isize {
+        self.effects.iter().map(|e| e.total_bonus()).sum::<isize>()
+    }
+
+    /// The passive score for this roll: 10 plus every bonus that isn't rolled, with 5 more for
+    /// advantage or 5 less for disadvantage.
+    pub fn passive(&self, name: String) -> PassiveResult {
+        let mut bonuses = vec![(vec!["base".to_string()], 10)];
+        bonuses.extend(
+            self.effects
+                .iter()
+                .filter(|effect| effect.bonus != 0)
+                .map(|effect| (effect.path.clone(), effect.bonus)),
+        );
+        let advantages = self
+            .advantages
+            .iter()
+            .map(|(_, advantage)| advantage.clone())
+            .collect::<Vec<Advantage>>();
+        let sources = self.advantages.iter().map(|(path, _)| path.iter().join(" / ")).join(", ");
+        match Advantage::combine(&advantages) {
+            Some(Advantage::Advantage) => bonuses.push((vec![format!("Advantage ({})", sources)], 5)),
+            Some(Advantage::Disadvantage) => bonuses.push((vec![format!("Disadvantage ({})", sources)], -5)),
+            None => {}
+        }
+        PassiveResult { name, bonuses }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PassiveResult {
+    name: String,
+    bonuses: Vec<(Vec<String>, isize)>,
+}
+
+impl PassiveResult {
+    pub fn total(&self) -> isize {
+        self.bonuses.iter().map(|(_, bonus)| bonus).sum()
+    }
+}
+
+impl Render for PassiveResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Passive {}\n", <dyn Render>::tab(indent), self.name))?;
+        for (path, bonus) in &self.bonuses {
+            out.write_fmt(format_args!(
+                "{}{}: {}\n",
+                <dyn Render>::tab(indent + 1),
+                path.iter().join(" / "),
+                bonus
+            ))?;
+        }
+        out.write_fmt(format_args!("{}Total: {}\n", <dyn Render>::tab(indent), self.total()))?;
+        Ok(())
+    }
 }
 
 impl Render for RollResult {
@@ -91,11 +271,47 @@ impl Render for RollResult {
             "{}Results\n",
             (0..indent).map(|_| '\t').collect::<String>()
         ))?;
+        if !self.advantages.is_empty() {
+            let advantages = self
+                .advantages
+                .iter()
+                .map(|(_, advantage)| advantage.clone())
+                .collect::<Vec<Advantage>>();
+            match Advantage::combine(&advantages) {
+                Some(advantage) => out.write_fmt(format_args!(
+                    "{}{}: {}\n",
+                    (0..indent + 1).map(|_| '\t').collect::<String>(),
+                    advantage,
+                    self.advantages
+                        .iter()
+                        .map(|(path, _)| path.iter().join(" / "))
+                        .join(", ")
+                ))?,
+                None => out.write_fmt(format_args!(
+                    "{}Advantage and Disadvantage cancel out: {}\n",
+                    (0..indent + 1).map(|_| '\t').collect::<String>(),
+                    self.advantages
+                        .iter()
+                        .map(|(path, advantage)| format!("{} ({})", path.iter().join(" / "), advantage))
+                        .join(", ")
+                ))?,
+            }
+        }
+        if self.critical {
+            out.write_fmt(format_args!(
+                "{}Critical: natural {}\n",
+                (0..indent + 1).map(|_| '\t').collect::<String>(),
+                self.natural().unwrap_or_default()
+            ))?;
+        }
         for effect in &self.effects {
             effect.render(indent + 1, out)?;
         }
-        let total_result: isize = self.effects.iter().map(|e| e.total_bonus()).sum::<isize>();
-        out.write_fmt(format_args!("Total: {}\n", total_result))?;
+        out.write_fmt(format_args!(
+            "{}Total: {}\n",
+            (0..indent).map(|_| '\t').collect::<String>(),
+            self.total()
+        ))?;
 
         Ok(())
     }
@@ -114,7 +330,7 @@ impl EffectResult {
             + self
                 .rolled_dice
                 .iter()
-                .map(|r| r.results.iter().sum::<isize>())
+                .map(|r| r.total())
                 .sum::<isize>()
     }
 }
@@ -123,10 +339,12 @@ impl Render for EffectResult {
     fn render(&self, indent: usize, out: &mut Write) -> Result<()> {
         let rolls = self.rolled_dice.iter().flat_map(|r| text(r)).join(" + ");
 
-        let bonus = if self.bonus > 0 {
+        let bonus = if rolls.is_empty() {
+            self.bonus.to_string()
+        } else if self.bonus > 0 {
             format!("{} + {}", rolls, self.bonus)
         } else if self.bonus < 0 {
-            format!("{} - {}", rolls, self.bonus)
+            format!("{} - {}", rolls, self.bonus.abs())
         } else {
             rolls
         };
@@ -144,13 +362,68 @@ impl Render for EffectResult {
 pub struct Dice {
     count: isize,
     sides: isize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keep: Option<Keep>,
+}
+
+/// Which dice count towards the total when more dice are rolled than are kept, e.g. `4d6kh3`.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type", content = "value")]
+pub enum Keep {
+    Highest(isize),
+    Lowest(isize),
 }
 
 impl Dice {
+    pub fn new(count: isize, sides: isize) -> Dice {
+        Dice {
+            count,
+            sides,
+            keep: None,
+        }
+    }
+
+    pub fn keeping(&self, keep: Keep) -> Dice {
+        Dice {
+            keep: Some(keep),
+            ..self.clone()
+        }
+    }
+
+    /// A single d20 is rolled twice when the roll has advantage or disadvantage.
+    pub fn with_advantage(&self, advantage: &Option<Advantage>) -> Dice {
+        match advantage {
+            Some(advantage) if self.count == 1 && self.sides == 20 && self.keep.is_none() => {
+                let keep = match advantage {
+                    Advantage::Advantage => Keep::Highest(1),
+                    Advantage::Disadvantage => Keep::Lowest(1),
+                };
+                Dice::new(2, 20).keeping(keep)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Twice as many dice, as rolled for the damage of a critical hit.
+    pub fn doubled(&self) -> Dice {
+        self.times(2)
+    }
+
+    pub fn times(&self, times: isize) -> Dice {
+        Dice {
+            count: self.count * times,
+            sides: self.sides,
+            keep: self.keep.as_ref().map(|keep| match keep {
+                Keep::Highest(keep) => Keep::Highest(keep * times),
+                Keep::Lowest(keep) => Keep::Lowest(keep * times),
+            }),
+        }
+    }
+
     pub fn roll(&self) -> RolledDice {
         let mut rand = rand::thread_rng();
         let results = (0..self.count)
-            .map(|_| rand.gen_range(1..=self.sides))
+            .map(|_| DieResult::of(rand.gen_range(1..=self.sides)))
             .collect();
 
         RolledDice {
@@ -163,14 +436,109 @@ impl Dice {
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct RolledDice {
     dice: Dice,
-    results: Vec<isize>,
+    results: Vec<DieResult>,
+}
+
+/// The faces rolled for a single die, including any reroll, and the one that counts.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DieResult {
+    faces: Vec<isize>,
+    value: isize,
+}
+
+impl DieResult {
+    pub fn of(face: isize) -> DieResult {
+        DieResult {
+            faces: vec![face],
+            value: face,
+        }
+    }
+
+    pub fn is_rerolled(&self) -> bool {
+        self.faces.len() > 1
+    }
+}
+
+impl RolledDice {
+    /// Whether each result counts towards the total, in the same order as `results`.
+    pub fn kept(&self) -> Vec<bool> {
+        let (keep, highest) = match &self.dice.keep {
+            None => return self.results.iter().map(|_| true).collect(),
+            Some(Keep::Highest(keep)) => (*keep, true),
+            Some(Keep::Lowest(keep)) => (*keep, false),
+        };
+
+        let ranked = self
+            .results
+            .iter()
+            .map(|result| result.value)
+            .enumerate()
+            .sorted_by(|(_, a), (_, b)| if highest { b.cmp(a) } else { a.cmp(b) })
+            .map(|(index, _)| index)
+            .take(keep.max(0) as usize)
+            .collect::<Vec<usize>>();
+
+        (0..self.results.len())
+            .map(|index| ranked.contains(&index))
+            .collect()
+    }
+
+    /// The result of a d20 that counts towards the total, used to detect critical hits.
+    pub fn natural(&self) -> Option<isize> {
+        if self.dice.sides != 20 {
+            return None;
+        }
+        self.results
+            .iter()
+            .zip(self.kept())
+            .find(|(_, kept)| *kept)
+            .map(|(result, _)| result.value)
+    }
+
+    pub fn total(&self) -> isize {
+        self.results
+            .iter()
+            .zip(self.kept())
+            .filter(|(_, kept)| *kept)
+            .map(|(result, _)| result.value)
+            .sum()
+    }
+
+    /// Rerolls each die showing one of the reroll faces, at most once per die.
+    pub fn reroll(&mut self, reroll: &Reroll) {
+        if reroll.sides.is_some_and(|sides| sides != self.dice.sides) {
+            return;
+        }
+        let mut rand = rand::thread_rng();
+        for result in self.results.iter_mut() {
+            if !result.is_rerolled() && reroll.faces.contains(&result.value) {
+                let face = rand.gen_range(1..=self.dice.sides);
+                result.faces.push(face);
+                result.value = match reroll.keep {
+                    RerollKeep::New => face,
+                    RerollKeep::Higher => face.max(result.value),
+                };
+            }
+        }
+    }
 }
 
 fn text(rolled_dice: &RolledDice) -> Vec<String> {
     rolled_dice
         .results
         .iter()
-        .map(|result| format!("[{} / {}]", result, rolled_dice.dice.sides))
+        .zip(rolled_dice.kept())
+        .map(|(result, kept)| {
+            let faces = result.faces.iter().join("→");
+            let mut text = format!("[{} / {}]", faces, rolled_dice.dice.sides);
+            if result.faces.last() != Some(&result.value) {
+                text = format!("{} (kept {})", text, result.value);
+            }
+            if !kept {
+                text = format!("{} (dropped)", text);
+            }
+            text
+        })
         .collect()
 }
 
@@ -179,10 +547,7 @@ impl Render for RolledDice {
         out.write_fmt(format_args!(
             "{}{}\n",
             (0..indent).map(|_| '\t').collect::<String>(),
-            self.results
-                .iter()
-                .map(|result| format!("[{} / {}]", result, self.dice.sides))
-                .join(", ")
+            text(self).join(", ")
         ))?;
 
         Ok(())
@@ -191,6 +556,294 @@ impl Render for RolledDice {
 
 impl Display for Dice {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}d{}", self.count, self.sides)
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match &self.keep {
+            Some(Keep::Highest(keep)) => write!(f, "kh{}", keep),
+            Some(Keep::Lowest(keep)) => write!(f, "kl{}", keep),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The most dice, and the most sides on a die, that a single term of a dice expression can have.
+const MAX_DICE: isize = 1000;
+
+/// A roll written in standard dice notation, such as `2d6+1d4-1`, `d20` or `4d6kh3`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiceExpression {
+    pub roll: Roll,
+    pub modifier: isize,
+}
+
+impl DiceExpression {
+    pub fn parse(expression: &str) -> Result<DiceExpression> {
+        let expression = expression
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        if expression.is_empty() {
+            return Err(anyhow!("No dice expression was provided"));
+        }
+
+        let mut terms = vec![];
+        let mut term = String::new();
+        let mut sign = 1;
+        for c in expression.chars() {
+            if c == '+' || c == '-' {
+                if !term.is_empty() {
+                    terms.push((sign, term));
+                    term = String::new();
+                } else if !terms.is_empty() {
+                    return Err(anyhow!("Expected a term before '{}' in {}", c, expression));
+                }
+                sign = if c == '-' { -1 } else { 1 };
+            } else {
+                term.push(c);
+            }
+        }
+        if term.is_empty() {
+            return Err(anyhow!("Expression {} ends without a term", expression));
+        }
+        terms.push((sign, term));
+
+        let mut dice = vec![];
+        let mut modifier = 0;
+        for (sign, term) in terms {
+            if term.contains('d') {
+                if sign < 0 {
+                    return Err(anyhow!("Cannot subtract dice: -{}", term));
+                }
+                dice.push(DiceExpression::parse_dice(&term)?);
+            } else {
+                modifier += sign * DiceExpression::parse_number(&term, &term)?;
+            }
+        }
+
+        Ok(DiceExpression {
+            roll: Roll { dice },
+            modifier,
+        })
+    }
+
+    fn parse_dice(term: &str) -> Result<Dice> {
+        let (count, rest) = term
+            .split_once('d')
+            .ok_or_else(|| anyhow!("Invalid dice: {}", term))?;
+        let count = if count.is_empty() {
+            1
+        } else {
+            DiceExpression::parse_number(count, term)?
+        };
+
+        let (sides, keep) = match rest.find('k') {
+            Some(index) => {
+                let (sides, keep) = rest.split_at(index);
+                let keep = if let Some(keep) = keep.strip_prefix("kh") {
+                    Keep::Highest(DiceExpression::parse_number(keep, term)?)
+                } else if let Some(keep) = keep.strip_prefix("kl") {
+                    Keep::Lowest(DiceExpression::parse_number(keep, term)?)
+                } else {
+                    return Err(anyhow!("Expected kh or kl in {}", term));
+                };
+                (sides, Some(keep))
+            }
+            None => (rest, None),
+        };
+        let sides = DiceExpression::parse_number(sides, term)?;
+
+        if count < 1 || sides < 1 {
+            return Err(anyhow!("Dice must have at least one die and one side: {}", term));
+        }
+        if count > MAX_DICE || sides > MAX_DICE {
+            return Err(anyhow!("Dice can have at most {} dice and {} sides: {}", MAX_DICE, MAX_DICE, term));
+        }
+        match keep {
+            Some(Keep::Highest(keep)) | Some(Keep::Lowest(keep)) if keep < 1 || keep > count => {
+                Err(anyhow!("Cannot keep {} of {} dice: {}", keep, count, term))
+            }
+            _ => Ok(Dice { count, sides, keep }),
+        }
+    }
+
+    fn parse_number(value: &str, term: &str) -> Result<isize> {
+        value
+            .parse::<isize>()
+            .map_err(|_| anyhow!("Expected a number but found '{}' in {}", value, term))
+    }
+
+    pub fn roll(&self) -> RollResult {
+        let mut effects = self
+            .roll
+            .dice
+            .iter()
+            .map(|dice| EffectResult {
+                path: vec![dice.to_string()],
+                rolled_dice: vec![dice.roll()],
+                bonus: 0,
+            })
+            .collect::<Vec<EffectResult>>();
+
+        if self.modifier != 0 {
+            effects.push(EffectResult {
+                path: vec!["modifier".to_string()],
+                rolled_dice: vec![],
+                bonus: self.modifier,
+            });
+        }
+
+        RollResult::new(vec![], effects, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DieResult, Dice, DiceExpression, Keep, Roll, RollContext, RolledDice};
+    use crate::domain::character::Character;
+    use crate::domain::effect::{Advantage, Reroll, RerollKeep, RollBonus};
+
+    fn assert_parses(expression: &str, dice: Vec<Dice>, modifier: isize) {
+        assert_eq!(
+            DiceExpression::parse(expression).unwrap(),
+            DiceExpression {
+                roll: Roll { dice },
+                modifier
+            },
+            "Unexpected parse of {}",
+            expression
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_parses("d20", vec![Dice::new(1, 20)], 0);
+        assert_parses("2d6+1d4-1", vec![Dice::new(2, 6), Dice::new(1, 4)], -1);
+        assert_parses("2d6 + 1d4 + 3", vec![Dice::new(2, 6), Dice::new(1, 4)], 3);
+        assert_parses("-2+1d8", vec![Dice::new(1, 8)], -2);
+        assert_parses("4d6kh3", vec![Dice::new(4, 6).keeping(Keep::Highest(3))], 0);
+        assert_parses("2D20KL1", vec![Dice::new(2, 20).keeping(Keep::Lowest(1))], 0);
+        assert_parses("5", vec![], 5);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        vec![
+            "", "2d", "d", "xd6", "2d6+", "2d6++3", "-1d4", "4d6kh5", "4d6kx3", "0d6", "1d0", "1001d6",
+            "999999999d6", "1d1001",
+        ]
+        .into_iter()
+        .for_each(|expression| {
+            assert!(
+                DiceExpression::parse(expression).is_err(),
+                "Expected {} to be rejected",
+                expression
+            )
+        });
+        assert!(DiceExpression::parse("1000d1000").is_ok());
+    }
+
+    #[test]
+    fn test_keep() {
+        let rolled = RolledDice {
+            dice: Dice::new(4, 6).keeping(Keep::Highest(3)),
+            results: vec![3, 1, 6, 3].into_iter().map(DieResult::of).collect(),
+        };
+        assert_eq!(rolled.kept(), vec![true, false, true, true]);
+        assert_eq!(rolled.total(), 12);
+
+        let rolled = RolledDice {
+            dice: Dice::new(2, 20).keeping(Keep::Lowest(1)),
+            results: vec![17, 4].into_iter().map(DieResult::of).collect(),
+        };
+        assert_eq!(rolled.total(), 4);
+        assert_eq!(rolled.natural(), Some(4));
+        assert_eq!(Dice::new(1, 8).doubled(), Dice::new(2, 8));
+    }
+
+    #[test]
+    fn test_reroll() {
+        let mut rolled = RolledDice {
+            dice: Dice::new(3, 6),
+            results: vec![1, 2, 5].into_iter().map(DieResult::of).collect(),
+        };
+        rolled.reroll(&Reroll {
+            faces: vec![1, 2].into_iter().collect(),
+            sides: Some(6),
+            keep: RerollKeep::Higher,
+        });
+        assert!(rolled.results.iter().take(2).all(|result| result.is_rerolled()));
+        assert!(!rolled.results[2].is_rerolled());
+        assert!(rolled.results[0].value >= 1 && rolled.results[1].value >= 2);
+
+        let faces = rolled.results.clone();
+        rolled.reroll(&Reroll {
+            faces: (1..=6).collect(),
+            sides: None,
+            keep: RerollKeep::New,
+        });
+        assert_eq!(rolled.results[0], faces[0], "A die is only rerolled once");
+        assert!(rolled.results[2].is_rerolled());
+
+        let mut rolled = RolledDice {
+            dice: Dice::new(1, 20),
+            results: vec![DieResult::of(1)],
+        };
+        rolled.reroll(&Reroll {
+            faces: vec![1].into_iter().collect(),
+            sides: Some(8),
+            keep: RerollKeep::New,
+        });
+        assert!(!rolled.results[0].is_rerolled(), "Only dice with matching sides are rerolled");
+    }
+
+    #[test]
+    fn test_critical() {
+        let path = vec!["attack".to_string()];
+        let bonuses = vec![
+            (path.clone(), RollBonus::Roll(Roll::new(vec![Dice::new(1, 20)]))),
+            (path.clone(), RollBonus::CriticalRange(1)),
+        ];
+        let attack = RollContext {
+            critical_range: Some(20),
+            ..RollContext::default()
+        };
+        let character = Character::default();
+
+        assert!(Roll::calculate_with(&path, &attack, &character, bonuses.clone()).is_critical());
+        assert!(!Roll::calculate_with(&path, &RollContext::default(), &character, bonuses).is_critical());
+        assert!(!DiceExpression::parse("1d20").unwrap().roll().is_critical());
+    }
+
+    #[test]
+    fn test_passive() {
+        let path = vec!["skill".to_string(), "perception".to_string()];
+        let character = Character::default();
+        let passive = |advantages: Vec<Advantage>, context: Vec<Advantage>| {
+            let mut bonuses = vec![
+                (path.clone(), RollBonus::Roll(Roll::new(vec![Dice::new(1, 20)]))),
+                (path.clone(), RollBonus::Roll(Roll::new(vec![Dice::new(1, 4)]))),
+                (path.clone(), RollBonus::Modifier(3)),
+            ];
+            bonuses.extend(
+                advantages
+                    .into_iter()
+                    .map(|advantage| (path.clone(), RollBonus::Advantage(advantage))),
+            );
+            let context = RollContext {
+                advantages: context,
+                ..RollContext::default()
+            };
+            Roll::calculate_with(&path, &context, &character, bonuses)
+                .passive("Perception".to_string())
+                .total()
+        };
+
+        // Rolled dice, such as the d20 and Guidance's d4, don't count towards the passive score.
+        assert_eq!(passive(vec![], vec![]), 13);
+        assert_eq!(passive(vec![Advantage::Advantage], vec![]), 18);
+        assert_eq!(passive(vec![Advantage::Disadvantage], vec![]), 8);
+        assert_eq!(passive(vec![], vec![Advantage::Disadvantage]), 8);
+        assert_eq!(passive(vec![Advantage::Advantage, Advantage::Advantage], vec![]), 18);
+        assert_eq!(passive(vec![Advantage::Advantage], vec![Advantage::Disadvantage]), 13);
     }
 }
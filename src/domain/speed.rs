@@ -0,0 +1,106 @@
+use crate::render::Render;
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::cmp::max;
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum SpeedBonus {
+    /// Feet added to walking speed, or taken away when negative.
+    Modifier { modifier: isize },
+    /// Halves walking speed after any modifiers, as with the second level of exhaustion.
+    Halved,
+    /// Walking speed becomes 0, as with the fifth level of exhaustion.
+    Zero,
+}
+
+/// Walking speed in feet, along with every effect that changed it from the base speed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SpeedResult {
+    base: isize,
+    bonuses: Vec<(Vec<String>, SpeedBonus)>,
+}
+
+impl SpeedResult {
+    pub fn new(base: isize, bonuses: Vec<(Vec<String>, SpeedBonus)>) -> SpeedResult {
+        SpeedResult { base, bonuses }
+    }
+
+    pub fn speed(&self) -> isize {
+        let modified = max(
+            0,
+            self.base
+                + self
+                    .bonuses
+                    .iter()
+                    .map(|(_, bonus)| match bonus {
+                        SpeedBonus::Modifier { modifier } => *modifier,
+                        _ => 0,
+                    })
+                    .sum::<isize>(),
+        );
+        if self.has(&SpeedBonus::Zero) {
+            0
+        } else if self.has(&SpeedBonus::Halved) {
+            modified / 2
+        } else {
+            modified
+        }
+    }
+
+    fn has(&self, bonus: &SpeedBonus) -> bool {
+        self.bonuses.iter().any(|(_, candidate)| candidate == bonus)
+    }
+}
+
+impl Render for SpeedResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        if self.bonuses.is_empty() {
+            out.write_fmt(format_args!("{}Speed: {} ft\n", <dyn Render>::tab(indent), self.speed()))?;
+        } else {
+            out.write_fmt(format_args!(
+                "{}Speed: {} ft ({} ft base, {})\n",
+                <dyn Render>::tab(indent),
+                self.speed(),
+                self.base,
+                self.bonuses
+                    .iter()
+                    .map(|(path, bonus)| match bonus {
+                        SpeedBonus::Modifier { modifier } => format!("{:+} ft {}", modifier, path.join(" / ")),
+                        SpeedBonus::Halved => format!("halved by {}", path.join(" / ")),
+                        SpeedBonus::Zero => format!("reduced to 0 by {}", path.join(" / ")),
+                    })
+                    .join(", ")
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SpeedBonus, SpeedResult};
+
+    #[test]
+    fn test_speed() {
+        let encumbered = (vec!["Encumbered".to_string()], SpeedBonus::Modifier { modifier: -10 });
+        let exhaustion = |bonus: SpeedBonus| (vec!["exhaustion".to_string()], bonus);
+
+        assert_eq!(SpeedResult::new(30, vec![]).speed(), 30);
+        assert_eq!(SpeedResult::new(30, vec![encumbered.clone()]).speed(), 20);
+        assert_eq!(
+            SpeedResult::new(35, vec![encumbered.clone(), exhaustion(SpeedBonus::Halved)]).speed(),
+            12
+        );
+        assert_eq!(
+            SpeedResult::new(30, vec![exhaustion(SpeedBonus::Halved), exhaustion(SpeedBonus::Zero)]).speed(),
+            0
+        );
+        assert_eq!(
+            SpeedResult::new(5, vec![encumbered, exhaustion(SpeedBonus::Halved)]).speed(),
+            0
+        );
+    }
+}
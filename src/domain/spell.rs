@@ -0,0 +1,536 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::damage::DamageType;
+use crate::domain::effect::{Advantage, Range, RollBonus};
+use crate::domain::roll::{Roll, RollContext, RollResult};
+use crate::domain::spell_slots::{self, ordinal};
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+/// A spell from the compendium at `~/.dnd-cli/spells.json`, keyed by name.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Spell {
+    /// The spell's level, 0 for a cantrip.
+    pub level: isize,
+    pub school: School,
+    pub casting_time: String,
+    pub range: String,
+    #[serde(default)]
+    pub components: Vec<Component>,
+    /// The material component, if the spell has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub concentration: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<SpellResolution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub damage: Option<SpellDamage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum School {
+    Abjuration,
+    Conjuration,
+    Divination,
+    Enchantment,
+    Evocation,
+    Illusion,
+    Necromancy,
+    Transmutation,
+}
+
+impl Display for School {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Component {
+    Verbal,
+    Somatic,
+    Material,
+}
+
+impl Display for Component {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Component::Verbal => f.write_str("V"),
+            Component::Somatic => f.write_str("S"),
+            Component::Material => f.write_str("M"),
+        }
+    }
+}
+
+/// How the spell affects its targets: a spell attack roll, or a saving throw against the caster's
+/// spell save DC.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum SpellResolution {
+    Attack {
+        #[serde(default = "Range::melee")]
+        range: Range,
+    },
+    Save {
+        ability: Ability,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SpellDamage {
+    pub damage: Roll,
+    pub damage_type: DamageType,
+    /// Extra damage for each slot level above the spell's level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_slot_level: Option<Roll>,
+}
+
+impl Spell {
+    fn describe(&self) -> String {
+        if self.level == 0 {
+            format!("{} cantrip", self.school)
+        } else {
+            format!("{} level {}", ordinal(self.level), self.school)
+        }
+    }
+
+    /// Only spell attacks have a range, so range-scoped effects don't apply to other spells.
+    fn range(&self) -> Option<Range> {
+        match &self.resolution {
+            Some(SpellResolution::Attack { range }) => Some(range.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Finds a spell in the compendium, ignoring case.
+pub fn find_spell<'a>(spells: &'a HashMap<String, Spell>, name: &str) -> Result<(String, &'a Spell)> {
+    spells
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(key, spell)| (key.clone(), spell))
+        .ok_or_else(|| anyhow!("No spell named {} in the compendium", name))
+}
+
+/// The spells a character knows, by name.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct Spellbook {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    known: BTreeMap<String, KnownSpell>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct KnownSpell {
+    /// The class the spell is cast with, which decides the spellcasting ability.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// Cantrips can always be cast, other spells only while prepared. Classes that know their
+    /// spells rather than preparing them keep every spell prepared.
+    #[serde(default)]
+    pub prepared: bool,
+}
+
+impl Spellbook {
+    pub fn get(&self, name: &str) -> Option<&KnownSpell> {
+        self.known.get(name)
+    }
+
+    pub fn add(&mut self, name: String, spell: KnownSpell) {
+        self.known.insert(name, spell);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        self.known
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("{} is not in the spellbook", name))
+    }
+
+    pub fn set_prepared(&mut self, name: &str, prepared: bool) -> Result<()> {
+        let spell = self
+            .known
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("{} is not in the spellbook", name))?;
+        spell.prepared = prepared;
+        Ok(())
+    }
+}
+
+/// Every spell in a spellbook along with its definition from the compendium.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SpellbookResult {
+    spells: Vec<(String, Option<Spell>, KnownSpell)>,
+}
+
+impl SpellbookResult {
+    pub fn new(spellbook: &Spellbook, spells: &HashMap<String, Spell>) -> SpellbookResult {
+        SpellbookResult {
+            spells: spellbook
+                .known
+                .iter()
+                .map(|(name, known)| (name.clone(), spells.get(name).cloned(), known.clone()))
+                .sorted_by_key(|(name, spell, _)| (spell.as_ref().map(|spell| spell.level), name.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl Render for SpellbookResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Spells\n", <dyn Render>::tab(indent)))?;
+        for (name, spell, known) in &self.spells {
+            let mut details = vec![spell
+                .as_ref()
+                .map_or("not in the compendium".to_string(), |spell| spell.describe())];
+            if let Some(class) = &known.class {
+                details.push(class.clone());
+            }
+            if known.prepared && spell.as_ref().is_some_and(|spell| spell.level > 0) {
+                details.push("prepared".to_string());
+            }
+            out.write_fmt(format_args!(
+                "{}{} ({})\n",
+                <dyn Render>::tab(indent + 1),
+                name,
+                details.join(", ")
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// A single casting of a spell from the spellbook.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cast {
+    pub name: String,
+    pub spell: Spell,
+    /// The level of the spell slot to use, by default the spell's level.
+    pub level: Option<isize>,
+    pub pact: bool,
+    pub advantages: Vec<Advantage>,
+}
+
+impl Cast {
+    /// Consumes a spell slot, then rolls the spell attack and damage. Cantrips don't use a slot and
+    /// their damage dice increase at levels 5, 11 and 17. Casting a concentration spell ends
+    /// concentration on any other spell.
+    pub fn cast(&self, character: &mut Character) -> Result<CastResult> {
+        let known = character
+            .spellbook()
+            .get(&self.name)
+            .cloned()
+            .ok_or_else(|| anyhow!("{} is not in the spellbook", self.name))?;
+        if self.spell.level > 0 && !known.prepared {
+            return Err(anyhow!("{} is not prepared", self.name));
+        }
+        let ability = character
+            .spellcasting_ability(known.class.as_deref())
+            .ok_or_else(|| anyhow!("No spellcasting class to cast {} with", self.name))?;
+
+        let classes = character.classes().to_vec();
+        let level = if self.spell.level == 0 {
+            0
+        } else if self.pact {
+            let pact_slots = spell_slots::pact_slots(&classes);
+            let level = pact_slots.map_or(0, |(_, level)| level);
+            if level < self.spell.level {
+                return Err(anyhow!(
+                    "Pact slots are too low level for {}, a {} level spell",
+                    self.name,
+                    ordinal(self.spell.level)
+                ));
+            }
+            character.spell_slots().use_pact_slot(pact_slots)?;
+            level
+        } else {
+            let level = self.level.unwrap_or(self.spell.level);
+            if level < self.spell.level {
+                return Err(anyhow!(
+                    "{} is a {} level spell and cannot be cast with a {} level slot",
+                    self.name,
+                    ordinal(self.spell.level),
+                    ordinal(level)
+                ));
+            }
+            character
+                .spell_slots()
+                .use_slot(level, &spell_slots::maximum_slots(&classes))?;
+            level
+        };
+
+        let context = RollContext {
+            ability: Some(ability.clone()),
+            range: self.spell.range(),
+            advantages: self.advantages.clone(),
+            critical: false,
+            critical_range: Some(20),
+        };
+        let source = vec![self.name.clone()];
+        let attack = match &self.spell.resolution {
+            Some(SpellResolution::Attack { .. }) => Some(Roll::calculate_with(
+                &self.path("attack"),
+                &context,
+                character,
+                vec![
+                    (source.clone(), RollBonus::Ability(ability.clone())),
+                    (source.clone(), RollBonus::Proficiency),
+                ],
+            )),
+            _ => None,
+        };
+        let save = match &self.spell.resolution {
+            Some(SpellResolution::Save { ability: save }) => Some((
+                save.clone(),
+                8 + character.proficiency_bonus() + character.get_ability_score(ability.clone()).modifier(),
+            )),
+            _ => None,
+        };
+
+        let critical = attack.as_ref().is_some_and(|attack| attack.is_critical());
+        let damage = self.spell.damage.as_ref().map(|damage| {
+            let context = RollContext {
+                advantages: vec![],
+                critical,
+                critical_range: None,
+                ..context.clone()
+            };
+            let mut bonuses = vec![(
+                source.clone(),
+                RollBonus::Roll(damage.damage.times(cantrip_tier(&self.spell, character))),
+            )];
+            if let (Some(per_slot_level), true) = (&damage.per_slot_level, level > self.spell.level) {
+                bonuses.push((
+                    vec![self.name.clone(), format!("{} level", ordinal(level))],
+                    RollBonus::Roll(per_slot_level.times(level - self.spell.level)),
+                ));
+            }
+            (
+                damage.damage_type.clone(),
+                Roll::calculate_with(&self.path("damage"), &context, character, bonuses),
+            )
+        });
+
+        let ended_concentration = if self.spell.concentration {
+            character
+                .set_concentration(Some(self.name.clone()))
+                .filter(|previous| previous != &self.name)
+        } else {
+            None
+        };
+
+        Ok(CastResult {
+            name: self.name.clone(),
+            spell: self.spell.clone(),
+            ended_concentration,
+            level,
+            pact: self.pact,
+            attack,
+            save,
+            damage,
+        })
+    }
+
+    fn path(&self, roll: &str) -> Vec<String> {
+        vec![roll.to_string(), "spell".to_string(), self.name.clone()]
+    }
+}
+
+fn cantrip_tier(spell: &Spell, character: &Character) -> isize {
+    if spell.level > 0 {
+        return 1;
+    }
+    match character.total_level() {
+        level if level >= 17 => 4,
+        level if level >= 11 => 3,
+        level if level >= 5 => 2,
+        _ => 1,
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CastResult {
+    name: String,
+    spell: Spell,
+    ended_concentration: Option<String>,
+    level: isize,
+    pact: bool,
+    attack: Option<RollResult>,
+    save: Option<(Ability, isize)>,
+    damage: Option<(DamageType, RollResult)>,
+}
+
+impl Render for CastResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let tab = <dyn Render>::tab(indent);
+        let inner = <dyn Render>::tab(indent + 1);
+        out.write_fmt(format_args!("{}Cast {} ({})", tab, self.name, self.spell.describe()))?;
+        if self.pact {
+            out.write_fmt(format_args!(" with a {} level pact slot", ordinal(self.level)))?;
+        } else if self.level > 0 {
+            out.write_fmt(format_args!(" with a {} level slot", ordinal(self.level)))?;
+        }
+        out.write_fmt(format_args!("\n"))?;
+
+        out.write_fmt(format_args!("{}Casting Time: {}\n", inner, self.spell.casting_time))?;
+        out.write_fmt(format_args!("{}Range: {}\n", inner, self.spell.range))?;
+        if !self.spell.components.is_empty() {
+            out.write_fmt(format_args!(
+                "{}Components: {}",
+                inner,
+                self.spell.components.iter().join(", ")
+            ))?;
+            match &self.spell.material {
+                Some(material) => out.write_fmt(format_args!(" ({})\n", material))?,
+                None => out.write_fmt(format_args!("\n"))?,
+            }
+        }
+        if self.spell.concentration {
+            out.write_fmt(format_args!("{}Concentration\n", inner))?;
+        }
+        if let Some(previous) = &self.ended_concentration {
+            out.write_fmt(format_args!("{}Stopped concentrating on {}\n", inner, previous))?;
+        }
+        if let Some(attack) = &self.attack {
+            out.write_fmt(format_args!("{}Spell Attack\n", tab))?;
+            attack.render(indent + 1, out)?;
+        }
+        if let Some((ability, dc)) = &self.save {
+            out.write_fmt(format_args!("{}{} Saving Throw: DC {}\n", tab, ability, dc))?;
+        }
+        if let Some((damage_type, damage)) = &self.damage {
+            let critical = self.attack.as_ref().is_some_and(|attack| attack.is_critical());
+            out.write_fmt(format_args!(
+                "{}{} ({})\n",
+                tab,
+                if critical { "Critical Damage" } else { "Damage" },
+                damage_type
+            ))?;
+            damage.render(indent + 1, out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cast, Spell};
+    use crate::domain::ability_score::Ability;
+    use crate::domain::character::{test::character, Character};
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    /// A wizard with 16 Intelligence who has prepared `spell`, with +2 to melee rolls.
+    fn wizard(level: isize, spell: &str) -> Character {
+        character(json!({
+            "ability_scores": { "intelligence": { "value": 16 } },
+            "classes": [{ "name": "Wizard", "level": level }],
+            "features": {
+                "Melee Training": {
+                    "effects": [{
+                        "type": "Roll",
+                        "bonus": { "type": "Modifier", "value": 2 },
+                        "scope": { "range": { "type": "Melee" } },
+                    }],
+                },
+            },
+            "spellbook": { "known": { spell: { "prepared": true } } },
+        }))
+    }
+
+    /// A spell whose damage dice all roll 1, so the damage is the number of dice.
+    fn spell(level: isize, resolution: Value, dice: isize) -> Spell {
+        serde_json::from_value(json!({
+            "level": level,
+            "school": "Evocation",
+            "casting_time": "1 action",
+            "range": "60 feet",
+            "resolution": resolution,
+            "damage": {
+                "damage": { "dice": [{ "count": dice, "sides": 1 }] },
+                "damage_type": "Fire",
+                "per_slot_level": { "dice": [{ "count": 1, "sides": 1 }] },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn cast(name: &str, spell: Spell, level: Option<isize>) -> Cast {
+        Cast {
+            name: name.to_string(),
+            spell,
+            level,
+            pact: false,
+            advantages: vec![],
+        }
+    }
+
+    fn damage(cast: &Cast, character: &mut Character) -> isize {
+        cast.cast(character).unwrap().damage.unwrap().1.total()
+    }
+
+    fn save() -> Value {
+        json!({ "type": "Save", "ability": "Dexterity" })
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut character = wizard(3, "burning-hands");
+        let burning_hands = spell(1, save(), 3);
+
+        cast("burning-hands", burning_hands.clone(), Some(2)).cast(&mut character).unwrap();
+        cast("burning-hands", burning_hands.clone(), Some(2)).cast(&mut character).unwrap();
+        assert!(cast("burning-hands", burning_hands.clone(), Some(2)).cast(&mut character).is_err());
+        assert!(cast("burning-hands", burning_hands.clone(), Some(3)).cast(&mut character).is_err());
+        cast("burning-hands", burning_hands, None).cast(&mut character).unwrap();
+        assert_eq!(
+            character.spell_slots().regain(None),
+            vec![(1, 1), (2, 2)].into_iter().collect::<BTreeMap<isize, isize>>()
+        );
+
+        let mut character = wizard(3, "fire-bolt");
+        cast("fire-bolt", spell(0, save(), 1), None).cast(&mut character).unwrap();
+        assert!(character.spell_slots().regain(None).is_empty());
+    }
+
+    #[test]
+    fn test_upcast() {
+        let burning_hands = spell(1, save(), 3);
+        assert_eq!(damage(&cast("burning-hands", burning_hands.clone(), None), &mut wizard(5, "burning-hands")), 3);
+        assert_eq!(damage(&cast("burning-hands", burning_hands, Some(3)), &mut wizard(5, "burning-hands")), 5);
+    }
+
+    #[test]
+    fn test_cantrip_tiers() {
+        let sacred_flame = cast("sacred-flame", spell(0, save(), 1), None);
+        for (level, dice) in &[(1, 1), (4, 1), (5, 2), (10, 2), (11, 3), (17, 4), (20, 4)] {
+            assert_eq!(damage(&sacred_flame, &mut wizard(*level, "sacred-flame")), *dice);
+        }
+    }
+
+    #[test]
+    fn test_save_dc() {
+        let sacred_flame = cast("sacred-flame", spell(0, save(), 1), None);
+        assert_eq!(
+            sacred_flame.cast(&mut wizard(1, "sacred-flame")).unwrap().save,
+            Some((Ability::Dexterity, 8 + 2 + 3))
+        );
+        assert_eq!(
+            sacred_flame.cast(&mut wizard(9, "sacred-flame")).unwrap().save,
+            Some((Ability::Dexterity, 8 + 4 + 3))
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        // Melee effects apply to melee spell attacks but not to spells without an attack roll.
+        let melee = json!({ "type": "Attack", "range": { "type": "Melee" } });
+        assert_eq!(damage(&cast("shocking-grasp", spell(0, melee, 1), None), &mut wizard(1, "shocking-grasp")), 3);
+        assert_eq!(damage(&cast("sacred-flame", spell(0, save(), 1), None), &mut wizard(1, "sacred-flame")), 1);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::domain::character::CharacterClass;
+use crate::render::Render;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Spell slots of each level for a full caster, the first entry being caster level 1.
+const SPELL_SLOTS: [[isize; 9]; 20] = [
+    [2, 0, 0, 0, 0, 0, 0, 0, 0],
+    [3, 0, 0, 0, 0, 0, 0, 0, 0],
+    [4, 2, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 2, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 1, 0, 0, 0, 0, 0],
+    [4, 3, 3, 2, 0, 0, 0, 0, 0],
+    [4, 3, 3, 3, 1, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 2, 1, 1],
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Spellcasting {
+    Full,
+    Half,
+    /// A half caster that rounds its caster level up, such as the Artificer.
+    HalfRoundedUp,
+    /// Such as the Eldritch Knight and Arcane Trickster subclasses.
+    Third,
+    /// Warlock pact magic, which has its own slots that recharge on a short rest.
+    Pact,
+    None,
+}
+
+impl Spellcasting {
+    /// The caster level this class contributes when multiclassing.
+    fn multiclass_level(&self, level: isize) -> isize {
+        match self {
+            Spellcasting::Full => level,
+            Spellcasting::Half => level / 2,
+            Spellcasting::HalfRoundedUp => (level + 1) / 2,
+            Spellcasting::Third => level / 3,
+            Spellcasting::Pact | Spellcasting::None => 0,
+        }
+    }
+
+    /// The caster level of a character with only this spellcasting class, who uses the class's own
+    /// table. Half and third casters gain their first slots at level 2 and 3 respectively.
+    fn single_class_level(&self, level: isize) -> isize {
+        match self {
+            Spellcasting::Half if level >= 2 => (level + 1) / 2,
+            Spellcasting::Third if level >= 3 => (level + 2) / 3,
+            Spellcasting::Half | Spellcasting::Third => 0,
+            _ => self.multiclass_level(level),
+        }
+    }
+}
+
+/// The maximum spell slots of each level, not including pact slots.
+pub fn maximum_slots(classes: &[CharacterClass]) -> BTreeMap<isize, isize> {
+    let casters = classes
+        .iter()
+        .filter(|class| !matches!(class.spellcasting(), Spellcasting::Pact | Spellcasting::None))
+        .collect::<Vec<&CharacterClass>>();
+    let caster_level = match casters.as_slice() {
+        [class] => class.spellcasting().single_class_level(class.level()),
+        _ => casters
+            .iter()
+            .map(|class| class.spellcasting().multiclass_level(class.level()))
+            .sum(),
+    };
+    if caster_level < 1 {
+        return BTreeMap::new();
+    }
+
+    SPELL_SLOTS[min(caster_level, 20) as usize - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(level, count)| (level as isize + 1, *count))
+        .collect()
+}
+
+/// The number and level of pact slots granted by Warlock levels.
+pub fn pact_slots(classes: &[CharacterClass]) -> Option<(isize, isize)> {
+    let level: isize = classes
+        .iter()
+        .filter(|class| class.spellcasting() == Spellcasting::Pact)
+        .map(|class| class.level())
+        .sum();
+    let count = match level {
+        level if level < 1 => return None,
+        1 => 1,
+        2..=10 => 2,
+        11..=16 => 3,
+        _ => 4,
+    };
+    Some((count, min(5, (level + 1) / 2)))
+}
+
+/// Spell slots used of each level and pact slots used. The maximum comes from the character's
+/// classes.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub struct SpellSlots {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    used: BTreeMap<isize, isize>,
+    #[serde(default)]
+    pact_used: isize,
+}
+
+impl SpellSlots {
+    pub fn use_slot(&mut self, level: isize, maximum: &BTreeMap<isize, isize>) -> Result<()> {
+        let used = self.used.get(&level).copied().unwrap_or(0);
+        if used >= maximum.get(&level).copied().unwrap_or(0) {
+            return Err(anyhow!("No level {} spell slots remaining", level));
+        }
+        self.used.insert(level, used + 1);
+        Ok(())
+    }
+
+    pub fn use_pact_slot(&mut self, pact_slots: Option<(isize, isize)>) -> Result<()> {
+        match pact_slots {
+            Some((count, _)) if self.pact_used < count => {
+                self.pact_used += 1;
+                Ok(())
+            }
+            Some(_) => Err(anyhow!("No pact slots remaining")),
+            None => Err(anyhow!("No pact slots, pact magic requires Warlock levels")),
+        }
+    }
+
+    /// Regains a slot of the given level, or every slot without one. Returns the regained slots by
+    /// level.
+    pub fn regain(&mut self, level: Option<isize>) -> BTreeMap<isize, isize> {
+        let regained = match level {
+            Some(level) => self
+                .used
+                .get(&level)
+                .filter(|used| **used > 0)
+                .map(|_| vec![(level, 1)].into_iter().collect())
+                .unwrap_or_default(),
+            None => self.used.clone(),
+        };
+        for (level, count) in &regained {
+            if let Some(used) = self.used.get_mut(level) {
+                *used -= count;
+            }
+        }
+        self.used.retain(|_, used| *used > 0);
+        regained
+    }
+
+    /// Regains every pact slot, returning the number regained.
+    pub fn regain_pact(&mut self) -> isize {
+        let regained = self.pact_used;
+        self.pact_used = 0;
+        regained
+    }
+}
+
+/// The remaining and maximum spell slots of a character.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SpellSlotsResult {
+    slots: Vec<(isize, isize, isize)>,
+    pact: Option<(isize, isize, isize)>,
+}
+
+impl SpellSlotsResult {
+    pub fn new(slots: &SpellSlots, classes: &[CharacterClass]) -> SpellSlotsResult {
+        SpellSlotsResult {
+            slots: maximum_slots(classes)
+                .into_iter()
+                .map(|(level, maximum)| {
+                    let used = slots.used.get(&level).copied().unwrap_or(0);
+                    (level, max(0, maximum - used), maximum)
+                })
+                .collect(),
+            pact: pact_slots(classes)
+                .map(|(count, level)| (level, max(0, count - slots.pact_used), count)),
+        }
+    }
+}
+
+pub fn ordinal(level: isize) -> String {
+    let suffix = match level {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{}{}", level, suffix)
+}
+
+impl Render for SpellSlotsResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!("{}Spell Slots\n", <dyn Render>::tab(indent)))?;
+        for (level, remaining, maximum) in &self.slots {
+            out.write_fmt(format_args!(
+                "{}{}: {} / {}\n",
+                <dyn Render>::tab(indent + 1),
+                ordinal(*level),
+                remaining,
+                maximum
+            ))?;
+        }
+        if let Some((level, remaining, maximum)) = &self.pact {
+            out.write_fmt(format_args!(
+                "{}Pact ({}): {} / {}\n",
+                <dyn Render>::tab(indent + 1),
+                ordinal(*level),
+                remaining,
+                maximum
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{maximum_slots, pact_slots};
+    use crate::domain::character::CharacterClass;
+    use std::collections::BTreeMap;
+
+    fn classes(classes: &[(&str, isize)]) -> Vec<CharacterClass> {
+        serde_json::from_value(serde_json::Value::Array(
+            classes
+                .iter()
+                .map(|(name, level)| serde_json::json!({ "name": name, "level": level }))
+                .collect(),
+        ))
+        .unwrap()
+    }
+
+    fn slots(slots: &[isize]) -> BTreeMap<isize, isize> {
+        slots
+            .iter()
+            .enumerate()
+            .map(|(level, count)| (level as isize + 1, *count))
+            .collect()
+    }
+
+    #[test]
+    fn test_maximum_slots() {
+        assert_eq!(maximum_slots(&classes(&[("Wizard", 5)])), slots(&[4, 3, 2]));
+        assert_eq!(maximum_slots(&classes(&[("Ranger", 1)])), slots(&[]));
+        assert_eq!(maximum_slots(&classes(&[("Ranger", 3)])), slots(&[3]));
+        assert_eq!(
+            maximum_slots(&classes(&[("Paladin", 3), ("Sorcerer", 2)])),
+            slots(&[4, 2])
+        );
+        assert_eq!(
+            maximum_slots(&classes(&[("Warlock", 3), ("Fighter", 5)])),
+            slots(&[])
+        );
+        assert_eq!(pact_slots(&classes(&[("Warlock", 3), ("Bard", 1)])), Some((2, 2)));
+        assert_eq!(pact_slots(&classes(&[("Warlock", 20)])), Some((4, 5)));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::domain::ability_score::Ability;
+use crate::domain::character::Character;
+use crate::domain::damage::DamageType;
+use crate::domain::effect::{Advantage, Range, RollBonus};
+use crate::domain::roll::{Roll, RollContext, RollResult};
+use crate::render::Render;
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Weapon {
+    pub damage: Roll,
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub category: WeaponCategory,
+    #[serde(default = "Range::melee")]
+    pub range: Range,
+    #[serde(default)]
+    pub properties: Vec<WeaponProperty>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub enum WeaponCategory {
+    #[default]
+    Simple,
+    Martial,
+}
+
+impl Display for WeaponCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum WeaponProperty {
+    /// Uses the better of Strength and Dexterity.
+    Finesse,
+    /// Can be thrown at the given range, using the same ability as a melee attack.
+    Thrown { normal: isize, long: isize },
+    /// Deals `damage` instead when wielded with two hands.
+    Versatile { damage: Roll },
+    /// Each attack uses one of the inventory item at `item`, e.g. `quiver/arrows`.
+    Ammunition { item: Vec<String> },
+    Light,
+    Heavy,
+    TwoHanded,
+    Reach,
+    Loading,
+}
+
+impl Weapon {
+    fn is_finesse(&self) -> bool {
+        self.properties.contains(&WeaponProperty::Finesse)
+    }
+
+    fn thrown_range(&self) -> Option<Range> {
+        self.properties.iter().find_map(|property| match property {
+            WeaponProperty::Thrown { normal, long } => Some(Range::Ranged {
+                normal: *normal,
+                long: *long,
+            }),
+            _ => None,
+        })
+    }
+
+    fn versatile_damage(&self) -> Option<Roll> {
+        self.properties.iter().find_map(|property| match property {
+            WeaponProperty::Versatile { damage } => Some(damage.clone()),
+            _ => None,
+        })
+    }
+
+    fn ammunition(&self) -> Option<Vec<String>> {
+        self.properties.iter().find_map(|property| match property {
+            WeaponProperty::Ammunition { item } => Some(item.clone()),
+            _ => None,
+        })
+    }
+}
+
+impl Display for Weapon {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.damage, self.damage_type)
+    }
+}
+
+/// A single attack made with a weapon from the inventory.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Attack {
+    pub item: Vec<String>,
+    pub weapon: Weapon,
+    pub thrown: bool,
+    pub two_handed: bool,
+    pub advantages: Vec<Advantage>,
+}
+
+impl Attack {
+    fn name(&self) -> String {
+        self.item.last().cloned().unwrap_or_default()
+    }
+
+    /// Ranged weapons that can be thrown, such as darts, are always thrown.
+    fn is_thrown(&self) -> bool {
+        self.thrown || (matches!(self.weapon.range, Range::Ranged { .. }) && self.weapon.thrown_range().is_some())
+    }
+
+    pub fn range(&self) -> Range {
+        if self.is_thrown() {
+            self.weapon
+                .thrown_range()
+                .unwrap_or_else(|| self.weapon.range.clone())
+        } else {
+            self.weapon.range.clone()
+        }
+    }
+
+    /// Finesse weapons use the better of Strength and Dexterity, other ranged weapons use
+    /// Dexterity and everything else, including thrown melee weapons, uses Strength.
+    pub fn ability(&self, character: &Character) -> Ability {
+        if self.weapon.is_finesse() {
+            let strength = character.get_ability_score(Ability::Strength).modifier();
+            let dexterity = character.get_ability_score(Ability::Dexterity).modifier();
+            if dexterity >= strength {
+                Ability::Dexterity
+            } else {
+                Ability::Strength
+            }
+        } else if self.weapon.range != Range::Melee && self.weapon.thrown_range().is_none() {
+            Ability::Dexterity
+        } else {
+            Ability::Strength
+        }
+    }
+
+    /// The inventory item used up by making this attack, if any.
+    pub fn consumes(&self) -> Option<Vec<String>> {
+        match self.weapon.ammunition() {
+            Some(ammunition) => Some(ammunition),
+            None if self.is_thrown() => Some(self.item.clone()),
+            None => None,
+        }
+    }
+
+    fn path(&self, roll: &str) -> Vec<String> {
+        vec![
+            roll.to_string(),
+            self.weapon.category.to_string(),
+            self.name(),
+        ]
+    }
+
+    fn damage(&self) -> Roll {
+        if self.two_handed {
+            self.weapon
+                .versatile_damage()
+                .unwrap_or_else(|| self.weapon.damage.clone())
+        } else {
+            self.weapon.damage.clone()
+        }
+    }
+
+    pub fn roll(&self, character: &Character) -> AttackResult {
+        let context = RollContext {
+            ability: Some(self.ability(character)),
+            range: Some(self.range()),
+            advantages: self.advantages.clone(),
+            critical: false,
+            critical_range: Some(20),
+        };
+        let to_hit = Roll::calculate(&self.path("attack"), &context, character);
+
+        let context = RollContext {
+            advantages: vec![],
+            critical: to_hit.is_critical(),
+            critical_range: None,
+            ..context
+        };
+        let damage = Roll::calculate_with(
+            &self.path("damage"),
+            &context,
+            character,
+            vec![(self.item.clone(), RollBonus::Roll(self.damage()))],
+        );
+
+        AttackResult {
+            item: self.item.clone(),
+            range: self.range(),
+            damage_type: self.weapon.damage_type.clone(),
+            to_hit,
+            damage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AttackResult {
+    item: Vec<String>,
+    range: Range,
+    damage_type: DamageType,
+    to_hit: RollResult,
+    damage: RollResult,
+}
+
+impl Render for AttackResult {
+    fn render(&self, indent: usize, out: &mut dyn Write) -> Result<()> {
+        out.write_fmt(format_args!(
+            "{}Attack with {} ({})\n",
+            <dyn Render>::tab(indent),
+            self.item.iter().join(" / "),
+            self.range
+        ))?;
+        self.to_hit.render(indent + 1, out)?;
+        if self.to_hit.is_critical() {
+            out.write_fmt(format_args!(
+                "{}Critical Damage ({})\n",
+                <dyn Render>::tab(indent),
+                self.damage_type
+            ))?;
+        } else {
+            out.write_fmt(format_args!(
+                "{}Damage ({})\n",
+                <dyn Render>::tab(indent),
+                self.damage_type
+            ))?;
+        }
+        self.damage.render(indent + 1, out)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Attack, Weapon, WeaponProperty};
+    use crate::domain::ability_score::Ability;
+    use crate::domain::character::test::character;
+    use crate::domain::damage::DamageType;
+    use crate::domain::effect::Range;
+    use crate::domain::roll::{Dice, Roll};
+    use serde_json::json;
+
+    fn attack(range: Range, properties: Vec<WeaponProperty>, thrown: bool) -> Attack {
+        Attack {
+            item: vec!["bandolier".to_string(), "weapon".to_string()],
+            weapon: Weapon {
+                damage: Roll::new(vec![Dice::new(1, 4)]),
+                damage_type: DamageType::Piercing,
+                category: Default::default(),
+                range,
+                properties,
+            },
+            thrown,
+            two_handed: false,
+            advantages: vec![],
+        }
+    }
+
+    #[test]
+    fn test_ability() {
+        let strong = character(json!({ "ability_scores": { "strength": { "value": 16 } } }));
+        let nimble = character(json!({ "ability_scores": { "dexterity": { "value": 16 } } }));
+        let ranged = Range::Ranged { normal: 80, long: 320 };
+        let thrown = WeaponProperty::Thrown { normal: 20, long: 60 };
+
+        assert_eq!(attack(Range::Melee, vec![], false).ability(&nimble), Ability::Strength);
+        assert_eq!(attack(ranged.clone(), vec![], false).ability(&strong), Ability::Dexterity);
+        assert_eq!(attack(Range::Melee, vec![thrown.clone()], true).ability(&nimble), Ability::Strength);
+        let finesse = attack(Range::Melee, vec![WeaponProperty::Finesse], false);
+        assert_eq!(finesse.ability(&strong), Ability::Strength);
+        assert_eq!(finesse.ability(&nimble), Ability::Dexterity);
+    }
+
+    #[test]
+    fn test_consumes() {
+        let item = vec!["bandolier".to_string(), "weapon".to_string()];
+        let ranged = Range::Ranged { normal: 20, long: 60 };
+        let thrown = WeaponProperty::Thrown { normal: 20, long: 60 };
+        let arrows = vec!["quiver".to_string(), "arrows".to_string()];
+
+        assert_eq!(attack(Range::Melee, vec![], false).consumes(), None);
+        assert_eq!(attack(Range::Melee, vec![thrown.clone()], false).consumes(), None);
+        assert_eq!(attack(Range::Melee, vec![thrown.clone()], true).consumes(), Some(item.clone()));
+        assert_eq!(attack(ranged.clone(), vec![thrown], false).consumes(), Some(item));
+        let bow = attack(ranged, vec![WeaponProperty::Ammunition { item: arrows.clone() }], false);
+        assert_eq!(bow.consumes(), Some(arrows));
+    }
+}
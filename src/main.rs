@@ -3,10 +3,9 @@ pub mod domain;
 pub mod render;
 mod store;
 
-use crate::domain::ability_score::Ability;
 use command::*;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 fn main() {
     let cmd = command::RootCmd::parse();
@@ -17,33 +16,128 @@ fn main() {
             println!("encountered an error {:?}", e)
     }
 }
+use crate::domain::armor_class::ArmorClassResult;
 use crate::domain::character::Character;
-use crate::domain::roll::Roll;
+use crate::domain::concentration::ConcentrationCheck;
+use crate::domain::currency::{Coins, Transaction, WalletResult};
+use crate::domain::damage::{Damage, DamageType};
+use crate::domain::inventory::{
+    AddContainerResult, AddItemResult, ContainerDetails, InventoryItem, InventoryListing, RemoveContainerMode, Weight,
+};
+use crate::domain::resource::ResourceResult;
+use crate::domain::rest;
+use crate::domain::spell::{find_spell, Cast, KnownSpell, Spell, SpellbookResult};
+use crate::domain::spell_slots::{self, SpellSlotsResult};
+use crate::domain::roll::{DiceExpression, Roll, RollContext};
+use crate::domain::weapon::Attack;
 use render::Render;
 use std::io::Write;
+use std::collections::HashMap;
 use std::path::{Component, PathBuf};
 
 fn handle(cmd: RootCmd) -> Result<()> {
-    let current_dir = std::env::current_dir()?;
-    let store = store::Store::new(current_dir)?;
     match cmd {
         RootCmd::Character { cmd } => {
+            let current_dir = std::env::current_dir()?;
+            let store = store::Store::new(current_dir)?;
             let mut character = store.load_character()?.clone();
             match cmd {
                 CharacterCmd::Roll { cmd } => {
-                    let name = cmd.to_path();
-                    handle_roll_cmd(name, &character)?;
+                    if handle_roll_cmd(cmd, &mut character)? {
+                        store.update_inventory(character.inventory().clone(), &[])?;
+                    }
                 }
                 CharacterCmd::Inventory { cmd } => {
-                    handle_inventory_cmd(cmd, &mut character)?;
-                    store.update_inventory(character.inventory().clone())?;
+                    let removed = handle_inventory_cmd(cmd, &mut character)?;
+                    store.update_inventory(character.inventory().clone(), &removed)?;
                 }
                 CharacterCmd::HitPoints { cmd } => {
                     handle_hitpoints_cmd(cmd, &mut character)?;
                     store.update_hit_points(character.hit_points().clone())?;
+                    store.update_concentration(character.concentration().cloned())?;
+                }
+                CharacterCmd::AbilityScores { cmd } => {
+                    handle_ability_scores_cmd(cmd, &character)?;
+                }
+                CharacterCmd::ArmorClass { cmd } => match cmd {
+                    ArmorClassCmd::Show => {
+                        render(&ArmorClassResult::calculate(&character))?;
+                    }
+                },
+                CharacterCmd::Rest { cmd } => {
+                    handle_rest_cmd(cmd, &mut character)?;
+                    store.update_rest(
+                        character.hit_points().clone(),
+                        character.hit_dice().clone(),
+                        character.resources().clone(),
+                        character.spell_slots().clone(),
+                        character.active_conditions().clone(),
+                    )?;
+                }
+                CharacterCmd::Resource { cmd } => {
+                    handle_resource_cmd(cmd, &mut character)?;
+                    store.update_resources(character.resources().clone())?;
+                }
+                CharacterCmd::SpellSlots { cmd } => {
+                    handle_spell_slots_cmd(cmd, &mut character)?;
+                    store.update_spell_slots(character.spell_slots().clone())?;
+                }
+                CharacterCmd::Spells { cmd } => {
+                    handle_spells_cmd(cmd, &mut character, &store.load_spells()?)?;
+                    store.update_spellbook(character.spellbook().clone())?;
+                }
+                CharacterCmd::Condition { cmd } => {
+                    handle_condition_cmd(cmd, &mut character)?;
+                    store.update_active_conditions(character.active_conditions().clone())?;
+                    store.update_hit_points(character.hit_points().clone())?;
+                }
+                CharacterCmd::Concentration { cmd } => {
+                    if cmd == ConcentrationCmd::End {
+                        character.set_concentration(None);
+                        store.update_concentration(None)?;
+                    }
+                    match character.concentration() {
+                        Some(spell) => println!("Concentrating on {}", spell),
+                        None => println!("Not concentrating"),
+                    }
+                }
+                CharacterCmd::Wallet { cmd } => {
+                    handle_wallet_cmd(cmd, &mut character)?;
+                    store.update_wallet(character.wallet().clone())?;
+                }
+                CharacterCmd::Passive { skill } => {
+                    let path = vec!["skill".to_string(), skill.to_path()];
+                    let context = RollContext {
+                        ability: Some(skill.ability()),
+                        ..RollContext::default()
+                    };
+                    render(&Roll::calculate(&path, &context, &character).passive(skill.to_path()))?;
+                }
+                CharacterCmd::Cast {
+                    spell,
+                    level,
+                    pact,
+                    advantage,
+                } => {
+                    let spells = store.load_spells()?;
+                    let (name, spell) = find_spell(&spells, &spell)?;
+                    let cast = Cast {
+                        name,
+                        spell: spell.clone(),
+                        level,
+                        pact,
+                        advantages: advantage.to_advantages(),
+                    };
+                    render(&cast.cast(&mut character)?)?;
+                    store.update_spell_slots(character.spell_slots().clone())?;
+                    store.update_concentration(character.concentration().cloned())?;
                 }
             }
         },
+        RootCmd::Roll { expr } => {
+            let expression = DiceExpression::parse(&expr)?;
+            render(&expression.roll())?;
+        },
         RootCmd::Completions { shell } => {
             completions::complete(shell);
         }
@@ -67,6 +161,157 @@ mod completions {
     }
 }
 
+fn handle_ability_scores_cmd(cmd: AbilityScoresCmd, character: &Character) -> Result<()> {
+    match cmd {
+        AbilityScoresCmd::Show { ability } => {
+            let abilities = match ability {
+                Some(ability) => vec![ability],
+                None => vec![
+                    command::Ability::Strength,
+                    command::Ability::Dexterity,
+                    command::Ability::Constitution,
+                    command::Ability::Intelligence,
+                    command::Ability::Wisdom,
+                    command::Ability::Charisma,
+                ],
+            };
+            for ability in abilities {
+                render(&character.ability_score_result(ability.to_ability()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_wallet_cmd(cmd: WalletCmd, character: &mut Character) -> Result<()> {
+    let transaction = match cmd {
+        WalletCmd::Add { amount } => {
+            let coins = Coins::parse(&amount.join(" "))?;
+            *character.wallet() = character.wallet().plus(&coins);
+            Some(Transaction::Add { coins })
+        }
+        WalletCmd::Spend { amount } => Some(character.wallet().spend(&Coins::parse(&amount.join(" "))?)?),
+        WalletCmd::Convert { amount, to } => {
+            Some(character.wallet().convert(&Coins::parse(&amount.join(" "))?, to.to_coin())?)
+        }
+        WalletCmd::Split { shares, amount, take } => {
+            let split = Coins::parse(&amount.join(" "))?.split(shares)?;
+            if let (true, Transaction::Split { share, .. }) = (take, &split) {
+                *character.wallet() = character.wallet().plus(share);
+            }
+            Some(split)
+        }
+        WalletCmd::Show => None,
+    };
+    render(&WalletResult {
+        transaction,
+        wallet: character.wallet().clone(),
+    })
+}
+
+fn handle_condition_cmd(cmd: ConditionCmd, character: &mut Character) -> Result<()> {
+    match cmd {
+        ConditionCmd::Add { name, level } => {
+            character.add_condition(&name, level)?;
+        }
+        ConditionCmd::Remove { name } => {
+            character.remove_condition(&name)?;
+        }
+        ConditionCmd::Show => {}
+    }
+    render(character.active_conditions())?;
+    render(character.hit_points())
+}
+
+fn handle_spells_cmd(
+    cmd: SpellsCmd,
+    character: &mut Character,
+    spells: &HashMap<String, Spell>,
+) -> Result<()> {
+    match cmd {
+        SpellsCmd::Add {
+            spell,
+            class,
+            prepared,
+        } => {
+            let (name, _) = find_spell(spells, &spell)?;
+            character.spellbook().add(name, KnownSpell { class, prepared });
+        }
+        SpellsCmd::Remove { spell } => {
+            character.spellbook().remove(&spell)?;
+        }
+        SpellsCmd::Prepare { spell } => {
+            character.spellbook().set_prepared(&spell, true)?;
+        }
+        SpellsCmd::Unprepare { spell } => {
+            character.spellbook().set_prepared(&spell, false)?;
+        }
+        SpellsCmd::Show => {}
+    }
+    render(&SpellbookResult::new(character.spellbook(), spells))
+}
+
+fn handle_spell_slots_cmd(cmd: SpellSlotsCmd, character: &mut Character) -> Result<()> {
+    let classes = character.classes().to_vec();
+    match cmd {
+        SpellSlotsCmd::Use { pact: true, .. } => {
+            character.spell_slots().use_pact_slot(spell_slots::pact_slots(&classes))?;
+        }
+        SpellSlotsCmd::Use { level: Some(level), .. } => {
+            character
+                .spell_slots()
+                .use_slot(level, &spell_slots::maximum_slots(&classes))?;
+        }
+        SpellSlotsCmd::Use { level: None, .. } => {
+            return Err(anyhow!("Provide the level of the spell slot to use, or --pact"));
+        }
+        SpellSlotsCmd::Regain { pact: true, .. } => {
+            character.spell_slots().regain_pact();
+        }
+        SpellSlotsCmd::Regain { level, .. } => {
+            character.spell_slots().regain(level);
+        }
+        SpellSlotsCmd::Show => {}
+    }
+    render(&SpellSlotsResult::new(character.spell_slots(), &classes))
+}
+
+fn handle_resource_cmd(cmd: ResourceCmd, character: &mut Character) -> Result<()> {
+    match cmd {
+        ResourceCmd::Use { name, count } => {
+            let maximum = ResourceResult::of(character, &name)?.maximum();
+            character.resources().spend(&name, count, maximum)?;
+            render(&ResourceResult::of(character, &name)?)?;
+        }
+        ResourceCmd::Regain { name, count } => {
+            ResourceResult::of(character, &name)?;
+            character.resources().regain(&name, count);
+            render(&ResourceResult::of(character, &name)?)?;
+        }
+        ResourceCmd::Show { name: Some(name) } => {
+            render(&ResourceResult::of(character, &name)?)?;
+        }
+        ResourceCmd::Show { name: None } => {
+            for resource in ResourceResult::all(character) {
+                render(&resource)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_rest_cmd(cmd: RestCmd, character: &mut Character) -> Result<()> {
+    match cmd {
+        RestCmd::Short { hit_dice, sides } => {
+            render(&rest::short_rest(character, hit_dice, sides)?)?;
+        }
+        RestCmd::Long => {
+            render(&rest::long_rest(character)?)?;
+        }
+    }
+    render(character.hit_points())
+}
+
 fn handle_hitpoints_cmd(cmd: HitPointsCmd, character: &mut Character) -> Result<()> {
     match cmd {
         HitPointsCmd::Show => { }
@@ -74,10 +319,23 @@ fn handle_hitpoints_cmd(cmd: HitPointsCmd, character: &mut Character) -> Result<
             character.hit_points().increase_max(hit_points);
         }
         HitPointsCmd::Add { hit_points } => {
-            character.hit_points().add_current(hit_points);
+            character.hit_points().heal(hit_points);
         }
-        HitPointsCmd::Remove { hit_points } => {
-            character.hit_points().add_current(-hit_points);
+        HitPointsCmd::Remove { damage, damage_type } => {
+            let damage_type = match damage_type {
+                Some(damage_type) => Some(
+                    DamageType::of(&damage_type)
+                        .ok_or_else(|| anyhow!("Unknown damage type '{}'", damage_type))?,
+                ),
+                None => None,
+            };
+            let damage = Damage::parse(&damage.join(" "), damage_type)?;
+            let effects = character.all_effects();
+            let result = character.hit_points().take_damage(damage, &effects);
+            render(&result)?;
+            if let Some(check) = ConcentrationCheck::after_damage(character, result.total()) {
+                render(&check)?;
+            }
         }
         HitPointsCmd::AddTemporary { hit_points } => {
             character.hit_points().add_temporary(hit_points);
@@ -85,32 +343,102 @@ fn handle_hitpoints_cmd(cmd: HitPointsCmd, character: &mut Character) -> Result<
         HitPointsCmd::ResetTemporary => {
             character.hit_points().reset_temporary();
         }
+        HitPointsCmd::ModifyMax { hit_points } => {
+            character.hit_points().add_max_modifier(hit_points);
+        }
+        HitPointsCmd::ResetMaxModifier => {
+            character.hit_points().reset_max_modifier();
+        }
         HitPointsCmd::Reset => {
             character.hit_points().reset();
         }
+        HitPointsCmd::DeathSave { advantage } => {
+            let hit_points = character.hit_points().clone();
+            if hit_points.is_conscious() || hit_points.is_dead() || hit_points.is_stable() {
+                println!("Death saving throws are only made while dying");
+            } else {
+                let context = RollContext {
+                    advantages: advantage.to_advantages(),
+                    ..RollContext::default()
+                };
+                let path = vec!["saving-throw".to_string(), "death".to_string()];
+                let result = Roll::calculate(&path, &context, character);
+                render(&result)?;
+                let natural = result
+                    .natural()
+                    .ok_or_else(|| anyhow!("A death saving throw needs a d20, none was rolled"))?;
+                character.hit_points().death_save(natural, result.total());
+            }
+        }
     }
 
     render(character.hit_points())?;
     Ok(())
 }
-fn handle_roll_cmd(name: Vec<String>, character: &Character) -> Result<()> {
-    match name.first().map(|n| n.as_str()) {
-        Some("ability") => name
-            .get(1)
-            .map(|s| s.clone())
-            .and_then(Ability::of)
-            .map(|ability| {
-                let modifier = character.get_ability_score(ability).modifier();
-                println!("1d20+{}", modifier)
-            })
-            .unwrap_or_else(|| println!("Invalid ability path: {:?}", name)),
+/// Returns whether the roll used up anything in the inventory.
+fn handle_roll_cmd(cmd: RollCmd, character: &mut Character) -> Result<bool> {
+    if let RollCmd::Attack { item, thrown, two_handed, consume, .. } = &cmd {
+        return handle_attack(
+            parse_inventory_path(item.clone()),
+            *thrown,
+            *two_handed,
+            *consume,
+            cmd.to_context(),
+            character,
+        );
+    }
+
+    let name = cmd.to_path();
+    match name.first() {
         Some(_) => {
-            let calc_result = Roll::calculate(&name, &character);
+            let calc_result = Roll::calculate(&name, &cmd.to_context(), character);
             render(&calc_result)?;
         }
         None => println!("Nothing to roll for {:?}", name),
     };
-    Ok(())
+    Ok(false)
+}
+
+fn handle_attack(
+    item: Vec<String>,
+    thrown: bool,
+    two_handed: bool,
+    consume: bool,
+    context: RollContext,
+    character: &mut Character,
+) -> Result<bool> {
+    let weapon = match character.inventory().get(&item) {
+        Some(InventoryItem::Object { weapon: Some(weapon), .. }) => weapon.clone(),
+        Some(_) => {
+            println!("{} is not a weapon", item.join(" / "));
+            return Ok(false);
+        }
+        None => {
+            println!("{}: there was nothing there", item.join(" / "));
+            return Ok(false);
+        }
+    };
+    let attack = Attack {
+        item,
+        weapon,
+        thrown,
+        two_handed,
+        advantages: context.advantages,
+    };
+
+    let consumed = match attack.consumes() {
+        Some(consumed) if consume => Some(character.inventory().add_item(consumed, -1)?),
+        _ => None,
+    };
+    match &consumed {
+        Some(result @ AddItemResult::Success { .. }) => {
+            render(&attack.roll(character))?;
+            render(result)?;
+        }
+        Some(result) => render(result)?,
+        None => render(&attack.roll(character))?,
+    }
+    Ok(consumed.is_some())
 }
 
 fn parse_inventory_path(name: PathBuf) -> Vec<String> {
@@ -123,14 +451,16 @@ fn parse_inventory_path(name: PathBuf) -> Vec<String> {
             Component::Normal(name) => name.to_str().map(|s| s.to_string())
         }).collect()
 }
-fn handle_inventory_cmd(cmd: InventoryCmd, character: &mut Character) -> Result<()> {
+
+/// Returns the paths the inventory no longer has anything at.
+fn handle_inventory_cmd(cmd: InventoryCmd, character: &mut Character) -> Result<Vec<Vec<String>>> {
     match cmd {
         InventoryCmd::Add { name, count } => {
             let name = parse_inventory_path(name);
             let result = character.inventory().add_item(name.clone(), count)?;
             render(character.inventory())?;
             render(&result)?;
-            Ok(())
+            Ok(vec![])
         }
         InventoryCmd::Remove { name, count } => {
             let name = parse_inventory_path(name);
@@ -138,23 +468,69 @@ fn handle_inventory_cmd(cmd: InventoryCmd, character: &mut Character) -> Result<
             render(character.inventory())?;
             render(&result)?;
 
-            Ok(())
+            Ok(vec![])
         }
-        InventoryCmd::Show => {
+        InventoryCmd::Show { weight: false, value: false } => {
             render(character.inventory())?;
-            Ok(())
+            Ok(vec![])
+        },
+        InventoryCmd::Show { weight, value } => {
+            render(&InventoryListing {
+                inventory: character.inventory_with_catalog(),
+                weight,
+                value,
+            })?;
+            Ok(vec![])
         },
+        InventoryCmd::Equip { name } => {
+            let result = character.inventory().set_equipped(parse_inventory_path(name), true);
+            render(&result)?;
+            Ok(vec![])
+        }
+        InventoryCmd::Unequip { name } => {
+            let result = character.inventory().set_equipped(parse_inventory_path(name), false);
+            render(&result)?;
+            Ok(vec![])
+        }
+        InventoryCmd::Weight => {
+            render(&character.encumbrance())?;
+            render(&character.speed())?;
+            Ok(vec![])
+        }
+        InventoryCmd::Move { from, to, count, rename } => {
+            let result = character
+                .inventory()
+                .move_item(parse_inventory_path(from), parse_inventory_path(to), count, rename);
+            render(&result)?;
+            Ok(result.removed().into_iter().collect())
+        }
         InventoryCmd::Container { cmd } => {
             match cmd {
-                InventoryContainerCmd::Add { name } => {
+                InventoryContainerCmd::Add { name, weight, weightless_contents } => {
+                    let weight = weight.map(Weight::from_pounds).transpose()?;
                     let name = parse_inventory_path(name);
-                    let result = character.inventory().add_container(name)?;
+                    let result = character.inventory().add_container(name.clone())?;
+                    if let AddContainerResult::Success { .. } = result {
+                        character.inventory().set_container_details(&name, ContainerDetails {
+                            weight,
+                            weightless_contents,
+                        });
+                    }
                     render(&result)?;
-                    Ok(())
+                    Ok(vec![])
                 },
-                InventoryContainerCmd::Remove { name } => {
-
-                    Ok(())
+                InventoryContainerCmd::Remove { name, spill, discard } => {
+                    let mode = if spill {
+                        RemoveContainerMode::Spill
+                    } else if discard {
+                        RemoveContainerMode::Discard
+                    } else {
+                        RemoveContainerMode::IfEmpty
+                    };
+                    let result = character.inventory().remove_container(parse_inventory_path(name), mode);
+                    render(character.inventory())?;
+                    render(&result)?;
+                    Ok(result.removed().into_iter().collect())
                 }
             }
         }
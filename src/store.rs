@@ -1,5 +1,5 @@
 use crate::domain::character::Character;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use dirs::home_dir;
 pub struct Store {
     storage_dir: PathBuf,
@@ -24,14 +24,23 @@ impl Store {
     }
 }
 
-use crate::domain::inventory::{Inventory, InventoryItem};
+use crate::domain::inventory::{ContainerDetails, Inventory, InventoryItem};
 use itertools::Itertools;
 use serde_json::Value;
+use crate::domain::condition::Conditions;
+use crate::domain::currency::Coins;
+use crate::domain::hit_dice::HitDice;
 use crate::domain::hit_points::HitPoints;
+use crate::domain::resource::Resources;
+use crate::domain::spell::{Spell, Spellbook};
+use crate::domain::spell_slots::SpellSlots;
 use std::fs::FileType;
 use std::path::PathBuf;
 use std::collections::HashMap;
 
+/// The hidden file in a container's directory that holds the details of the container itself.
+const CONTAINER_DETAILS: &str = ".container";
+
 fn merge(a: Value, b: Value) -> Value {
     match (a, b) {
         (Value::Object(a), Value::Object(b)) => Value::Object(
@@ -65,6 +74,15 @@ impl Store {
              self.path_for("character.json".to_string())]
     }
 
+    /// The spell compendium, kept next to the template.
+    pub fn load_spells(&self) -> Result<HashMap<String, Spell>> {
+        let home = home_dir().and_then(|p| p.to_str().map(|s| s.to_string())).unwrap_or("".to_string());
+        let path = format!("{}/.dnd-cli/spells.json", home);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Could not read the spell compendium at {}: {}", path, e))?;
+        Ok(serde_json::from_str(content.as_str())?)
+    }
+
     pub fn load_file(template: Value, path: String) -> Result<Value> {
         let content = std::fs::read_to_string(path)?;
         let value: Value = serde_json::from_str(content.as_str())?;
@@ -80,7 +98,8 @@ impl Store {
         let character: Character = serde_json::value::from_value(value)?;
         let inventory = Store::load_inventory(self.storage_dir.clone())?;
         let inventory = Inventory::new(inventory);
-        let character = character.with_inventory(inventory);
+        let mut character = character.with_inventory(inventory);
+        character.apply_hit_point_effects();
         Ok(character)
 
 
@@ -94,7 +113,17 @@ impl Store {
         // Ok(character)
     }
 
-    pub fn update_inventory(&self, inventory: Inventory) -> Result<()> {
+    /// Writes the inventory after deleting the files and directories at `removed`, the paths the
+    /// inventory no longer has anything at, such as a removed container.
+    pub fn update_inventory(&self, inventory: Inventory, removed: &[Vec<String>]) -> Result<()> {
+        for path in removed.iter().filter(|path| !path.is_empty()) {
+            let path = path.iter().fold(self.storage_dir.clone(), |path, name| path.join(name));
+            if path.is_dir() {
+                std::fs::remove_dir_all(path)?;
+            } else if path.is_file() {
+                std::fs::remove_file(path)?;
+            }
+        }
         Store::write_inventory(self.storage_dir.clone(), inventory.items())?;
         // let content = std::fs::read_to_string(self.path_for(format!("characters/{}.json", name)))?;
         // let mut character: Character = serde_json::from_str(content.as_str())?;
@@ -104,10 +133,59 @@ impl Store {
     }
 
     pub fn update_hit_points(&self, hit_points: HitPoints) -> Result<()> {
+        self.update_character(|character| character.with_hit_points(hit_points))
+    }
+
+    /// Everything a rest changes, written at once so that a failed write can't leave a rest half
+    /// recorded.
+    pub fn update_rest(
+        &self,
+        hit_points: HitPoints,
+        hit_dice: HitDice,
+        resources: Resources,
+        spell_slots: SpellSlots,
+        active_conditions: Conditions,
+    ) -> Result<()> {
+        self.update_character(|character| {
+            character
+                .with_hit_points(hit_points)
+                .with_hit_dice(hit_dice)
+                .with_resources(resources)
+                .with_spell_slots(spell_slots)
+                .with_active_conditions(active_conditions)
+        })
+    }
+
+    pub fn update_wallet(&self, wallet: Coins) -> Result<()> {
+        self.update_character(|character| character.with_wallet(wallet))
+    }
+
+    pub fn update_resources(&self, resources: Resources) -> Result<()> {
+        self.update_character(|character| character.with_resources(resources))
+    }
+
+    pub fn update_spell_slots(&self, spell_slots: SpellSlots) -> Result<()> {
+        self.update_character(|character| character.with_spell_slots(spell_slots))
+    }
+
+    pub fn update_spellbook(&self, spellbook: Spellbook) -> Result<()> {
+        self.update_character(|character| character.with_spellbook(spellbook))
+    }
+
+    pub fn update_concentration(&self, concentration: Option<String>) -> Result<()> {
+        self.update_character(|character| character.with_concentration(concentration))
+    }
+
+    pub fn update_active_conditions(&self, active_conditions: Conditions) -> Result<()> {
+        self.update_character(|character| character.with_active_conditions(active_conditions))
+    }
+
+    /// Rewrites `character.json` on its own, without the template, with a single change applied.
+    fn update_character(&self, update: impl FnOnce(&Character) -> Character) -> Result<()> {
         let file_name = self.path_for("character.json".to_string());
         let content = std::fs::read_to_string(file_name.clone())?;
-        let mut character: Character = serde_json::from_str(content.as_str())?;
-        let updated = serde_json::to_string(&character.with_hit_points(hit_points))?;
+        let character: Character = serde_json::from_str(content.as_str())?;
+        let updated = serde_json::to_string(&update(&character))?;
         std::fs::write(file_name, updated)?;
         Ok(())
     }
@@ -116,13 +194,17 @@ impl Store {
         for (name, item) in items.iter() {
             let child_path =path.join(name);
             match item {
-                InventoryItem::Object {
-                    count
-                } => {
+                InventoryItem::Object { .. } => {
                     std::fs::write(child_path, serde_json::to_string_pretty(item)?)?;
                 },
-                InventoryItem::Container { items } => {
+                InventoryItem::Container { items, details } => {
                     std::fs::create_dir_all(child_path.clone())?;
+                    let details_path = child_path.join(CONTAINER_DETAILS);
+                    if !details.is_empty() {
+                        std::fs::write(details_path, serde_json::to_string_pretty(details)?)?;
+                    } else if details_path.exists() {
+                        std::fs::remove_file(details_path)?;
+                    }
                     Store::write_inventory(child_path, items)?;
                 }
             };
@@ -139,8 +221,10 @@ impl Store {
             .flat_map(|entry| {
             let path = entry.path();
             if path.is_dir() {
-                Store::load_inventory(path.clone()).ok().and_then(|items|
-                    path.file_name().map(|name| (name.to_str().unwrap_or("").to_string(), InventoryItem::Container { items })))
+                Store::load_inventory(path.clone()).ok().and_then(|items| {
+                    let details = Store::load_container_details(path.join(CONTAINER_DETAILS));
+                    path.file_name().map(|name| (name.to_str().unwrap_or("").to_string(), InventoryItem::Container { items, details }))
+                })
 
             } else {
                 Store::load_item(path.clone()).ok().and_then(|item|
@@ -151,6 +235,14 @@ impl Store {
         Ok(result)
     }
 
+    /// Containers without a details file have none.
+    fn load_container_details(path: PathBuf) -> ContainerDetails {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
     fn load_item(path: PathBuf) -> Result<InventoryItem> {
         let contents = std::fs::read_to_string(path)?;
         let item = serde_json::from_str(&contents)?;